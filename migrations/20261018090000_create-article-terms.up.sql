@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE blog.article_terms
+(
+    article_id INT references blog.articles (id),
+    term       VARCHAR(255) NOT NULL,
+    weight     REAL         NOT NULL,
+    PRIMARY KEY (article_id, term)
+);
+
+CREATE INDEX article_terms_term_idx ON blog.article_terms (term);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE blog.article_revisions
+(
+    id          SERIAL PRIMARY KEY,
+    article_id  INT references blog.articles (id),
+    title       VARCHAR(255)       NOT NULL,
+    content     TEXT               NOT NULL,
+    summary     VARCHAR(255)       NOT NULL,
+    state       blog.article_state NOT NULL,
+    category_id INT,
+    tags_id     INT[]              NOT NULL,
+    created_at  TIMESTAMPTZ        NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX article_revisions_article_id_idx ON blog.article_revisions (article_id);
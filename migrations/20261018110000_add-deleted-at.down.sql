@@ -0,0 +1,24 @@
+-- Add down migration script here
+CREATE OR REPLACE VIEW blog.article2tag AS
+SELECT article_id, ARRAY_AGG(tag_id) as tag_ids
+FROM blog.article_tag
+group by article_id;
+
+CREATE OR REPLACE VIEW blog.tag2article AS
+SELECT tag_id, ARRAY_AGG(article_id) as article_ids
+FROM blog.article_tag
+group by tag_id;
+
+DROP INDEX blog.tags_name_key;
+ALTER TABLE blog.tags
+    ADD CONSTRAINT tags_name_key UNIQUE (name);
+DROP INDEX blog.categories_name_key;
+ALTER TABLE blog.categories
+    ADD CONSTRAINT categories_name_key UNIQUE (name);
+
+ALTER TABLE blog.tags
+    DROP COLUMN deleted_at;
+ALTER TABLE blog.categories
+    DROP COLUMN deleted_at;
+ALTER TABLE blog.articles
+    DROP COLUMN deleted_at;
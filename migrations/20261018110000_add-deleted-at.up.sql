@@ -0,0 +1,28 @@
+-- Add up migration script here
+ALTER TABLE blog.articles
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE blog.categories
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE blog.tags
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- a name in the trash bin can be taken again
+ALTER TABLE blog.categories
+    DROP CONSTRAINT categories_name_key;
+CREATE UNIQUE INDEX categories_name_key ON blog.categories (name) WHERE deleted_at IS NULL;
+ALTER TABLE blog.tags
+    DROP CONSTRAINT tags_name_key;
+CREATE UNIQUE INDEX tags_name_key ON blog.tags (name) WHERE deleted_at IS NULL;
+
+-- rows in the trash bin no longer show on the other side
+CREATE OR REPLACE VIEW blog.article2tag AS
+SELECT article_id, ARRAY_AGG(tag_id) as tag_ids
+FROM blog.article_tag
+WHERE tag_id NOT IN (SELECT id FROM blog.tags WHERE deleted_at IS NOT NULL)
+group by article_id;
+
+CREATE OR REPLACE VIEW blog.tag2article AS
+SELECT tag_id, ARRAY_AGG(article_id) as article_ids
+FROM blog.article_tag
+WHERE article_id NOT IN (SELECT id FROM blog.articles WHERE deleted_at IS NOT NULL)
+group by tag_id;
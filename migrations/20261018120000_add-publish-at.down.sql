@@ -0,0 +1,16 @@
+-- Add down migration script here
+-- an enum value cannot be dropped, 'scheduled' stays in blog.article_state unused
+UPDATE blog.articles
+SET state = 'hidden'
+WHERE state = 'scheduled';
+UPDATE blog.article_revisions
+SET state = 'hidden'
+WHERE state = 'scheduled';
+
+DROP INDEX blog.articles_expire_at_idx;
+DROP INDEX blog.articles_publish_at_idx;
+
+ALTER TABLE blog.articles
+    DROP COLUMN expire_at;
+ALTER TABLE blog.articles
+    DROP COLUMN publish_at;
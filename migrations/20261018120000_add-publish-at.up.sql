@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TYPE blog.article_state ADD VALUE 'scheduled';
+
+ALTER TABLE blog.articles
+    ADD COLUMN publish_at TIMESTAMPTZ;
+ALTER TABLE blog.articles
+    ADD COLUMN expire_at TIMESTAMPTZ;
+
+CREATE INDEX articles_publish_at_idx ON blog.articles (publish_at);
+CREATE INDEX articles_expire_at_idx ON blog.articles (expire_at);
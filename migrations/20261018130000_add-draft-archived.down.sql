@@ -0,0 +1,2 @@
+-- Add down migration script here
+-- an enum value cannot be dropped, 'draft' and 'archived' stay in blog.article_state unused
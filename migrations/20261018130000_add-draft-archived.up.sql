@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TYPE blog.article_state ADD VALUE 'draft';
+ALTER TYPE blog.article_state ADD VALUE 'archived';
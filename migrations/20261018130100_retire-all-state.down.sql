@@ -0,0 +1,7 @@
+-- Add down migration script here
+UPDATE blog.articles
+SET state = 'hidden'
+WHERE state IN ('draft', 'archived');
+UPDATE blog.article_revisions
+SET state = 'hidden'
+WHERE state IN ('draft', 'archived');
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- 'all' was written by articles created without a state; it is a filter, not a state
+UPDATE blog.articles
+SET state = 'draft'
+WHERE state = 'all';
+UPDATE blog.article_revisions
+SET state = 'draft'
+WHERE state = 'all';
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE blog.articles
+    DROP COLUMN version;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- bumped by every edit, so an editor can tell the article changed since it was loaded
+ALTER TABLE blog.articles
+    ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX blog.categories_parent_id_idx;
+
+ALTER TABLE blog.categories
+    DROP COLUMN parent_id;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- a category without a parent is a root; purging a parent makes its children roots
+ALTER TABLE blog.categories
+    ADD COLUMN parent_id INTEGER REFERENCES blog.categories (id) ON DELETE SET NULL;
+
+CREATE INDEX categories_parent_id_idx ON blog.categories (parent_id);
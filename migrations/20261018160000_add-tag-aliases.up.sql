@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- names of the tags merged into another one, resolved to it when entered again
+CREATE TABLE blog.tag_aliases
+(
+    name   VARCHAR(255) PRIMARY KEY,
+    tag_id INT NOT NULL REFERENCES blog.tags (id) ON DELETE CASCADE
+);
+
+CREATE INDEX tag_aliases_tag_id_idx ON blog.tag_aliases (tag_id);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+-- tag 0 cannot be told apart from one given on purpose, the links stay
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- an article without any tag carries tag 0
+INSERT INTO blog.article_tag (article_id, tag_id)
+SELECT id, 0
+FROM blog.articles AS a
+WHERE NOT EXISTS (SELECT 1 FROM blog.article_tag WHERE article_id = a.id);
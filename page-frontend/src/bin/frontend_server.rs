@@ -7,6 +7,7 @@ use tower_http::services::ServeDir;
 
 use page_frontend::shared_state::SharedState;
 use util_pb::blog_service_client::BlogServiceClient;
+use util_pb::transfer::display_timezone;
 
 #[tokio::main]
 async fn main() {
@@ -15,13 +16,14 @@ async fn main() {
 
     let backend_url = std::env::var("BACKEND_URL").unwrap();
     let frontend_url = std::env::var("FRONTEND_URL").unwrap();
+    let timezone = display_timezone().name().to_string();
 
     let tera = Tera::new("page-frontend/templates/**/*.html").unwrap();
     let client = BlogServiceClient::connect(format!("http://{}", backend_url))
         .await
         .unwrap();
 
-    let shared_state = SharedState::new(tera, client);
+    let shared_state = SharedState::new(tera, client, timezone);
     let static_svc = get_service(ServeDir::new("page-frontend/templates/assets")).handle_error(
         |err| async move {
             (
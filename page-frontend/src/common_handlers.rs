@@ -1,14 +1,13 @@
+use std::collections::{HashMap, HashSet};
+
 use axum::headers::HeaderMap;
 use axum::http::StatusCode;
 use axum::response::Html;
-use futures::future;
-use tonic::transport::Channel;
+use serde::{Deserialize, Serialize};
 use tonic::Request;
 
-use util_pb::blog_service_client::BlogServiceClient;
-use util_pb::create_request::Create;
 use util_pb::query_request::Query;
-use util_pb::{ArticleState, Category, CreateRequest, QueryTag, Tag};
+use util_pb::{ArticleState, Category, ResolveTagsRequest, Tag};
 
 use crate::errors::{FrontendError, Result};
 use crate::shared_state::SharedState;
@@ -16,6 +15,23 @@ use crate::shared_state::SharedState;
 pub type Redirect = (StatusCode, HeaderMap);
 pub type TeraHtml = Html<String>;
 
+pub const PAGE_SIZE: i32 = 20;
+
+/// `?cursor=` of the paginated article lists, empty for the first page.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    #[serde(default)]
+    pub cursor: String,
+}
+
+/// The non-empty fields of `filter` as a query string, so a filtered page can be bookmarked.
+pub(crate) fn query_string<T: Serialize>(filter: &T) -> Result<String> {
+    let query = serde_urlencoded::to_string(filter)
+        .map_err(|e| FrontendError::InternalError(e.to_string()))?;
+    let pairs = query.split('&').filter(|pair| !pair.ends_with('='));
+    Ok(pairs.collect::<Vec<_>>().join("&"))
+}
+
 pub(crate) fn redirect_with_cookies(url: &str, cookies: Option<&str>) -> Redirect {
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -30,50 +46,36 @@ pub(crate) fn redirect_with_cookies(url: &str, cookies: Option<&str>) -> Redirec
     (StatusCode::FOUND, headers)
 }
 
-pub async fn create_if_not_exists_then_return_tag_id(
-    tag_name: String,
-    mut client: BlogServiceClient<Channel>,
-) -> Result<i32> {
-    let query = QueryTag {
-        ids: vec![],
-        name: tag_name.to_string(),
-    };
-    let tonic_req = Request::new(util_pb::QueryRequest {
-        query: Some(Query::QueryTag(query)),
-    });
-    let tonic_res = client.query(tonic_req).await?;
-    let mut res = tonic_res.into_inner().tags;
-
-    if res.len() == 1 {
-        Ok(res.pop().unwrap().id)
-    } else if res.is_empty() {
-        let create = Tag {
-            name: tag_name.to_string(),
-            ..Tag::default()
-        };
-        let tonic_req = Request::new(CreateRequest {
-            create: Some(Create::Tag(create)),
-        });
-        let tonic_res = client.create(tonic_req).await?;
-        Ok(tonic_res.into_inner().id)
-    } else {
-        let msg = format!("Unexpected tag response length: {}", res.len());
-        tracing::error!("{}", &msg);
-        Err(FrontendError::InternalError(msg))
-    }
+/// Tag names typed in a form: separated by `,`, `，` or `、`, trimmed, without blanks or a
+/// second spelling of one already given.
+pub fn split_tags(tags_str: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags_str
+        .split([',', '，', '、'])
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .map(String::from)
+        .collect()
 }
 
 pub async fn get_ids_from_tag_str(tags_str: &str, state: &SharedState) -> Result<Vec<i32>> {
-    let tag_client = state.client();
-
-    let tag_ids = future::try_join_all(
-        tags_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .map(|tag| create_if_not_exists_then_return_tag_id(tag, tag_client.clone())),
-    )
-    .await?;
-    Ok(tag_ids)
+    let req = Request::new(ResolveTagsRequest {
+        names: split_tags(tags_str),
+        ..Default::default()
+    });
+    let res = state.client().resolve_tags(req).await?.into_inner();
+    Ok(res.ids)
+}
+
+/// Ids of the tags named in `tags_str`, matched as tagging an article does but creating none;
+/// then the names of no tag.
+pub async fn find_tag_ids(tags_str: &str, state: &SharedState) -> Result<(Vec<i32>, Vec<String>)> {
+    let req = Request::new(ResolveTagsRequest {
+        names: split_tags(tags_str),
+        lookup_only: true,
+    });
+    let res = state.client().resolve_tags(req).await?.into_inner();
+    Ok((res.ids, res.unknown))
 }
 
 /* =================================================================
@@ -101,9 +103,120 @@ pub async fn get_tags(state: &SharedState) -> Vec<Tag> {
     res.tags
 }
 
+/// Categories depth first, each with its depth; one whose parent is not among them is a root.
+pub fn category_tree(categories: Vec<Category>) -> Vec<(usize, Category)> {
+    let ids = categories.iter().map(|c| c.id).collect::<HashSet<_>>();
+    let mut children = HashMap::<Option<i32>, Vec<Category>>::new();
+    for category in categories {
+        let parent_id = category.parent_id.filter(|id| ids.contains(id));
+        children.entry(parent_id).or_default().push(category);
+    }
+
+    let mut tree = Vec::new();
+    let mut stack = children
+        .remove(&None)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|category| (0, category))
+        .collect::<Vec<_>>();
+    while let Some((depth, category)) = stack.pop() {
+        let subcategories = children.remove(&Some(category.id)).unwrap_or_default();
+        stack.extend(subcategories.into_iter().rev().map(|c| (depth + 1, c)));
+        tree.push((depth, category));
+    }
+    tree
+}
+
+/// The category `id` and its ancestors, root first.
+pub fn category_path(categories: &[Category], id: i32) -> Vec<Category> {
+    let by_id = categories
+        .iter()
+        .map(|c| (c.id, c))
+        .collect::<HashMap<_, _>>();
+    let mut path = Vec::new();
+    let mut next = Some(id);
+    while let Some(category) = next.and_then(|id| by_id.get(&id)) {
+        // a cycle cannot be stored, but do not loop on one
+        if path.iter().any(|c: &Category| c.id == category.id) {
+            break;
+        }
+        path.push((*category).clone());
+        next = category.parent_id;
+    }
+    path.reverse();
+    path
+}
+
+/// Names of the article states in the order of their values, so `loop.index` of a template is the value.
 pub fn articles_states() -> Vec<&'static str> {
     vec![
         ArticleState::Published.as_str_name(),
         ArticleState::Hidden.as_str_name(),
+        ArticleState::Scheduled.as_str_name(),
+        ArticleState::Draft.as_str_name(),
+        ArticleState::Archived.as_str_name(),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: i32, parent_id: Option<i32>) -> Category {
+        Category {
+            id,
+            name: id.to_string(),
+            parent_id,
+            ..Category::default()
+        }
+    }
+
+    #[test]
+    fn split_tags_should_normalise_input() {
+        assert_eq!(
+            split_tags(" rust，Async、 web ,Rust,, "),
+            vec!["rust", "Async", "web"]
+        );
+        assert!(split_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn query_string_should_skip_empty_fields() {
+        let filter = [("title", "a b"), ("state", ""), ("tags", "x,y")];
+        assert_eq!(query_string(&filter).unwrap(), "title=a+b&tags=x%2Cy");
+        assert_eq!(query_string(&[("title", "")]).unwrap(), "");
+    }
+
+    #[test]
+    fn category_tree_should_nest_depth_first() {
+        // 4's parent is gone, so it is a root
+        let categories = vec![
+            category(1, None),
+            category(2, Some(3)),
+            category(3, Some(1)),
+            category(4, Some(9)),
+            category(5, Some(1)),
+        ];
+        let tree = category_tree(categories)
+            .into_iter()
+            .map(|(depth, c)| (depth, c.id))
+            .collect::<Vec<_>>();
+        assert_eq!(tree, vec![(0, 1), (1, 3), (2, 2), (1, 5), (0, 4)]);
+    }
+
+    #[test]
+    fn category_path_should_start_at_the_root() {
+        let categories = vec![
+            category(1, None),
+            category(2, Some(1)),
+            category(3, Some(2)),
+        ];
+        let path = category_path(&categories, 3)
+            .into_iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>();
+        assert_eq!(path, vec![1, 2, 3]);
+        assert!(category_path(&categories, 7).is_empty());
+    }
+}
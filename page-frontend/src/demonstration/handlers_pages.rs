@@ -1,12 +1,22 @@
-use axum::extract::Path;
+use std::collections::HashMap;
+
+use axum::extract::{self, Path};
 use axum::response::Html;
 use axum::Extension;
+use chrono::Datelike;
+use chrono_tz::Tz;
 use pulldown_cmark::{Options, Parser};
+use serde::Serialize;
 use tera::Context;
 use util_pb::query_request::Query;
+use util_pb::transfer::to_chrono;
+use util_pb::{ArchiveRequest, Article, ArticleProjection, SearchRequest, Visibility};
 
-use crate::common_handlers::{get_categories, get_tags, TeraHtml};
-use crate::errors::Result;
+use crate::common_handlers::{
+    category_path, category_tree, get_categories, get_tags, PageParams, TeraHtml, PAGE_SIZE,
+};
+use crate::demonstration::forms::SearchParams;
+use crate::errors::{FrontendError, Result};
 use crate::shared_state::SharedState;
 
 pub async fn page_index(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
@@ -21,7 +31,7 @@ pub async fn page_index(Extension(state): Extension<SharedState>) -> Result<Tera
 
 pub async fn page_categories(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
     let mut context = tera::Context::new();
-    let categories = get_categories(&state).await;
+    let categories = category_tree(get_categories(&state).await);
 
     context.insert("categories", &categories);
     let page = state
@@ -34,17 +44,26 @@ pub async fn page_categories(Extension(state): Extension<SharedState>) -> Result
 
 pub async fn page_category(
     Path(category_id): Path<i32>,
+    extract::Query(params): extract::Query<PageParams>,
     Extension(state): Extension<SharedState>,
 ) -> Result<TeraHtml> {
     let mut ctx = Context::new();
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryArticle(util_pb::QueryArticle {
             category_id,
+            include_descendants: true,
+            visibility: Visibility::Public as i32,
+            page_size: PAGE_SIZE,
+            projection: ArticleProjection::Summary as i32,
+            cursor: params.cursor.clone(),
             ..Default::default()
         })),
     };
-    let res = state.client().query(query).await?.into_inner().articles;
-    ctx.insert("articles", &res);
+    let res = state.client().query(query).await?.into_inner();
+    ctx.insert("articles", &res.articles);
+    ctx.insert("total", &res.total);
+    ctx.insert("cursor", &params.cursor);
+    ctx.insert("next_cursor", &res.next_cursor);
 
     let page = state.tera().render("demonstration/articles.html", &ctx)?;
     Ok(Html(page))
@@ -52,14 +71,22 @@ pub async fn page_category(
 
 pub async fn page_tags(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
     let mut ctx = Context::new();
-    let tags = get_tags(&state).await;
+    // a cloud of the tags holding public articles, sized by their number
+    let tags = get_tags(&state)
+        .await
+        .into_iter()
+        .filter(|tag| tag.published_count > 0)
+        .collect::<Vec<_>>();
+    let max_count = tags.iter().map(|tag| tag.published_count).max();
     ctx.insert("tags", &tags);
+    ctx.insert("max_count", &max_count.unwrap_or(1));
     let page = state.tera().render("demonstration/tags/tags.html", &ctx)?;
     Ok(Html(page))
 }
 
 pub async fn page_tag(
     Path(tag_id): Path<i32>,
+    extract::Query(params): extract::Query<PageParams>,
     Extension(state): Extension<SharedState>,
 ) -> Result<TeraHtml> {
     let mut ctx = Context::new();
@@ -67,11 +94,18 @@ pub async fn page_tag(
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryArticle(util_pb::QueryArticle {
             tags_id: vec![tag_id],
+            visibility: Visibility::Public as i32,
+            page_size: PAGE_SIZE,
+            projection: ArticleProjection::Summary as i32,
+            cursor: params.cursor.clone(),
             ..Default::default()
         })),
     };
-    let res = state.client().query(query).await?.into_inner().articles;
-    ctx.insert("articles", &res);
+    let res = state.client().query(query).await?.into_inner();
+    ctx.insert("articles", &res.articles);
+    ctx.insert("total", &res.total);
+    ctx.insert("cursor", &params.cursor);
+    ctx.insert("next_cursor", &res.next_cursor);
 
     let page = state.tera().render("demonstration/articles.html", &ctx)?;
     Ok(Html(page))
@@ -86,19 +120,157 @@ pub async fn page_show_article(
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryArticle(util_pb::QueryArticle {
             ids: vec![article_id],
+            visibility: Visibility::Public as i32,
             ..Default::default()
         })),
     };
-    let res = state.client().query(query).await?.into_inner().articles;
-    ctx.insert("article", &res[0]);
+    let mut res = state.client().query(query).await?.into_inner().articles;
+    let article = res
+        .pop()
+        .ok_or_else(|| FrontendError::NotFound(format!("No such article: {}", article_id)))?;
+    ctx.insert("article", &article);
+
+    let categories = get_categories(&state).await;
+    let breadcrumb = category_path(&categories, article.category_id);
+    ctx.insert("breadcrumb", &breadcrumb);
 
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
     let mut content = String::new();
-    let parser = Parser::new_ext(&res[0].content, options);
+    let parser = Parser::new_ext(&article.content, options);
     pulldown_cmark::html::push_html(&mut content, parser);
     ctx.insert("content", &content);
 
     let page = state.tera().render("demonstration/article.html", &ctx)?;
     Ok(Html(page))
 }
+
+#[derive(Serialize)]
+struct ArchiveMonth {
+    label: String,
+    count: i32,
+    articles: Vec<Article>,
+}
+
+/// The months with public articles, newest first, each with its articles; the articles of every
+/// month come in one query and are sorted into their month here.
+pub async fn page_archives(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+    let timezone: Tz = state.timezone().parse().map_err(|_| {
+        FrontendError::InternalError(format!("No such timezone: {}", state.timezone()))
+    })?;
+    let req = ArchiveRequest {
+        timezone: state.timezone().to_string(),
+        visibility: Visibility::Public as i32,
+    };
+    let buckets = state.client().archive(req).await?.into_inner().buckets;
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            visibility: Visibility::Public as i32,
+            projection: ArticleProjection::Summary as i32,
+            ..Default::default()
+        })),
+    };
+    let articles = state.client().query(query).await?.into_inner().articles;
+    let mut by_month: HashMap<(i32, u32), Vec<Article>> = HashMap::new();
+    for article in articles {
+        let Some(created_at) = &article.created_at else {
+            continue;
+        };
+        let local = to_chrono(created_at).with_timezone(&timezone);
+        by_month
+            .entry((local.year(), local.month()))
+            .or_default()
+            .push(article);
+    }
+
+    let months = buckets
+        .into_iter()
+        .map(|bucket| ArchiveMonth {
+            label: format!("{}-{:02}", bucket.year, bucket.month),
+            count: bucket.count,
+            articles: by_month
+                .remove(&(bucket.year, bucket.month as u32))
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    ctx.insert("months", &months);
+
+    let page = state.tera().render("demonstration/archives.html", &ctx)?;
+    Ok(Html(page))
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    article: &'a Article,
+    title: &'a str,
+    snippet: &'a str,
+    category: &'a str,
+    tags: Vec<&'a str>,
+}
+
+pub async fn page_search(
+    extract::Query(params): extract::Query<SearchParams>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+    let q = params.q.trim();
+    let page = params.page.max(0);
+    ctx.insert("q", q);
+    ctx.insert("page", &page);
+
+    let res = if q.is_empty() {
+        util_pb::SearchResponse::default()
+    } else {
+        let req = SearchRequest {
+            query: q.to_string(),
+            page_size: PAGE_SIZE,
+            page,
+            visibility: Visibility::Public as i32,
+        };
+        state.client().search(req).await?.into_inner()
+    };
+
+    let c_map = get_categories(&state)
+        .await
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect::<HashMap<_, _>>();
+    let t_map = get_tags(&state)
+        .await
+        .into_iter()
+        .map(|t| (t.id, t.name))
+        .collect::<HashMap<_, _>>();
+
+    let results = res
+        .hits
+        .iter()
+        .filter_map(|hit| {
+            let article = hit.article.as_ref()?;
+            Some(SearchResult {
+                article,
+                title: &hit.title,
+                snippet: &hit.snippet,
+                category: c_map
+                    .get(&article.category_id)
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                tags: article
+                    .tags_id
+                    .iter()
+                    .filter_map(|tag_id| t_map.get(tag_id).map(String::as_str))
+                    .collect(),
+            })
+        })
+        .collect::<Vec<_>>();
+    ctx.insert("results", &results);
+    ctx.insert("total", &res.total);
+    ctx.insert(
+        "has_next",
+        &((page as i64 + 1) * (PAGE_SIZE as i64) < res.total),
+    );
+
+    let page = state.tera().render("demonstration/search.html", &ctx)?;
+    Ok(Html(page))
+}
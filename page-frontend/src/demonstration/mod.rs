@@ -1,2 +1,3 @@
+mod forms;
 mod handlers_pages;
 pub mod routers;
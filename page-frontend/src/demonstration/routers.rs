@@ -10,4 +10,6 @@ pub fn demonstration_router() -> Router {
         .route("/tags", get(page_tags))
         .route("/tags/:tag_id", get(page_tag))
         .route("/articles/:article_id", get(page_show_article))
+        .route("/search", get(page_search))
+        .route("/archives", get(page_archives))
 }
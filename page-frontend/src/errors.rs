@@ -1,3 +1,4 @@
+use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 use tonic::Status;
@@ -19,6 +20,12 @@ pub enum FrontendError {
 
     #[error("Internal Error: {0}")]
     InternalError(String),
+
+    #[error("Not Found: {0}")]
+    NotFound(String),
+
+    #[error("Bad Request: {0}")]
+    BadRequest(String),
 }
 
 impl From<Status> for FrontendError {
@@ -29,6 +36,12 @@ impl From<Status> for FrontendError {
 
 impl IntoResponse for FrontendError {
     fn into_response(self) -> Response {
-        self.to_string().into_response()
+        match self {
+            FrontendError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()).into_response(),
+            FrontendError::BadRequest(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+            _ => self.to_string().into_response(),
+        }
     }
 }
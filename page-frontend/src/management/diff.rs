@@ -0,0 +1,179 @@
+use serde::Serialize;
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// One line of a rendered diff; `html` is escaped, with the changed words wrapped in `<del>`/`<ins>`.
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    pub kind: &'static str,
+    pub html: String,
+}
+
+/// Line-level diff of `old` and `new`, refined to words inside the replaced lines.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let mut lines = vec![];
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        match op {
+            DiffOp::Equal { old_index, len, .. } => {
+                for line in &old[old_index..old_index + len] {
+                    lines.push(DiffLine {
+                        kind: "equal",
+                        html: escape(line),
+                    });
+                }
+            }
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => {
+                for line in &old[old_index..old_index + old_len] {
+                    lines.push(DiffLine {
+                        kind: "delete",
+                        html: escape(line),
+                    });
+                }
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => {
+                for line in &new[new_index..new_index + new_len] {
+                    lines.push(DiffLine {
+                        kind: "insert",
+                        html: escape(line),
+                    });
+                }
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                let old = &old[old_index..old_index + old_len];
+                let new = &new[new_index..new_index + new_len];
+                let (deleted, inserted) = diff_words(&old.join("\n"), &new.join("\n"));
+                lines.extend(deleted.split('\n').map(|html| DiffLine {
+                    kind: "delete",
+                    html: html.to_string(),
+                }));
+                lines.extend(inserted.split('\n').map(|html| DiffLine {
+                    kind: "insert",
+                    html: html.to_string(),
+                }));
+            }
+        }
+    }
+    lines
+}
+
+/// Word-level diff of `old` and `new`, rendered as the old text with `<del>` and the new one with `<ins>`.
+pub fn diff_words(old: &str, new: &str) -> (String, String) {
+    let old = words(old);
+    let new = words(new);
+
+    let mut deleted = String::new();
+    let mut inserted = String::new();
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        match op {
+            DiffOp::Equal { .. } => {
+                deleted.push_str(&escape(&old[old_range].concat()));
+                inserted.push_str(&escape(&new[new_range].concat()));
+            }
+            _ => {
+                push_marked(&mut deleted, "del", &old[old_range].concat());
+                push_marked(&mut inserted, "ins", &new[new_range].concat());
+            }
+        }
+    }
+    (deleted, inserted)
+}
+
+/// Split `text` into words: runs of letters and digits, single CJK characters, and every other char alone.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() && !is_cjk(c) {
+            start.get_or_insert(i);
+            continue;
+        }
+        if let Some(s) = start.take() {
+            words.push(&text[s..i]);
+        }
+        words.push(&text[i..i + c.len_utf8()]);
+    }
+    if let Some(s) = start {
+        words.push(&text[s..]);
+    }
+    words
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extension B and later
+    )
+}
+
+/// Wrap `text` in `<tag>` line by line, so the mark never spans a line break.
+fn push_marked(html: &mut String, tag: &str, text: &str) {
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            html.push('\n');
+        }
+        if !line.is_empty() {
+            html.push_str(&format!("<{}>{}</{}>", tag, escape(line), tag));
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_should_split_cjk_chars() {
+        assert_eq!(
+            words("Rust 异步, ok"),
+            vec!["Rust", " ", "异", "步", ",", " ", "ok"]
+        );
+    }
+
+    #[test]
+    fn diff_words_should_mark_changes() {
+        let (deleted, inserted) = diff_words("我喜欢 Rust <3", "我讨厌 Rust <3");
+        assert_eq!(deleted, "我<del>喜欢</del> Rust &lt;3");
+        assert_eq!(inserted, "我<ins>讨厌</ins> Rust &lt;3");
+    }
+
+    #[test]
+    fn diff_lines_should_work() {
+        let lines = diff_lines("a\nb b\nc", "a\nb x\nc\nd");
+        let kinds = lines.iter().map(|line| line.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec!["equal", "delete", "insert", "equal", "insert"]);
+        assert_eq!(lines[1].html, "b <del>b</del>");
+        assert_eq!(lines[2].html, "b <ins>x</ins>");
+        assert_eq!(lines[4].html, "d");
+    }
+}
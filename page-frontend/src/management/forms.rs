@@ -1,6 +1,12 @@
-use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+use serde::{Deserialize, Serialize};
+
+use util_pb::get_summary;
+
+use crate::errors::{FrontendError, Result};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FormArticle {
     pub title: String,
     pub content: String,
@@ -8,14 +14,148 @@ pub struct FormArticle {
     pub summary: String,
     pub state: i32,
     pub tags: String,
+    /// `<input type="datetime-local">`, empty when not set
+    #[serde(default)]
+    pub publish_at: String,
+    #[serde(default)]
+    pub expire_at: String,
+    /// the version the edit was made on, 0 when unknown
+    #[serde(default)]
+    pub version: i32,
+}
+
+impl FormArticle {
+    /// The summary an edit saves: the one typed, or one made from the content when it is empty.
+    pub fn saved_summary(&self) -> String {
+        match self.summary.is_empty() {
+            true => get_summary(&self.content),
+            false => self.summary.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FormCategory {
     pub name: String,
+    /// empty for a root category
+    #[serde(default)]
+    pub parent_id: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FormTag {
     pub name: String,
 }
+
+/// Filters of the article query page, kept in its query string; empty fields filter nothing.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArticleFilter {
+    pub title: String,
+    /// an `ArticleState`
+    pub state: String,
+    pub category_id: String,
+    pub tags: String,
+    /// `any` for articles with one of the tags, every tag otherwise
+    pub tag_match: String,
+    /// YYYY-MM-DD
+    pub from_date: String,
+    pub to_date: String,
+}
+
+/// Filter of the category and tag query pages.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NameFilter {
+    pub name: String,
+}
+
+/// The bulk action bar of the article lists: what to do with the checked articles.
+#[derive(Debug, PartialEq)]
+pub enum BulkAction {
+    /// to an `ArticleState`
+    State(i32),
+    Category(i32),
+    /// tag names as typed
+    AddTags(String),
+    RemoveTags(String),
+    Delete,
+}
+
+/// The article lists a bulk action goes back to; the first when the form names none.
+const ARTICLE_LISTS: [&str; 2] = ["/management/articles", "/management/articles/query"];
+
+/// The form of the bulk action bar. The `ids` key repeats, so it is read from the pairs posted.
+#[derive(Debug, PartialEq)]
+pub struct BulkForm {
+    pub ids: Vec<i32>,
+    pub action: BulkAction,
+    /// the list the form was posted from, one of `ARTICLE_LISTS`
+    pub back: String,
+    /// the filters of that list, without its page and outcomes
+    pub filter: Vec<(String, String)>,
+    /// the page of the list the form was posted from
+    pub cursor: String,
+}
+
+impl TryFrom<Vec<(String, String)>> for BulkForm {
+    type Error = FrontendError;
+
+    fn try_from(pairs: Vec<(String, String)>) -> Result<Self> {
+        let mut ids = vec![];
+        let mut fields = HashMap::new();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "ids" => ids.push(value.parse::<i32>().map_err(|_| {
+                    FrontendError::BadRequest(format!("Not an article id: {}", value))
+                })?),
+                _ => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+        let field = |key: &str| fields.get(key).cloned().unwrap_or_default();
+        let number = |key: &str| {
+            field(key)
+                .parse::<i32>()
+                .map_err(|_| FrontendError::BadRequest(format!("Not a number: {}", field(key))))
+        };
+
+        let action = match field("action").as_str() {
+            "state" => BulkAction::State(number("state")?),
+            "category" => BulkAction::Category(number("category_id")?),
+            "add_tags" => BulkAction::AddTags(field("tags")),
+            "remove_tags" => BulkAction::RemoveTags(field("tags")),
+            "delete" => BulkAction::Delete,
+            action => {
+                return Err(FrontendError::BadRequest(format!(
+                    "No such bulk action: {}",
+                    action
+                )))
+            }
+        };
+        let back = field("back");
+        let (path, query) = back.split_once('?').unwrap_or((&back, ""));
+        let path = match path {
+            "" => ARTICLE_LISTS[0],
+            path => ARTICLE_LISTS
+                .into_iter()
+                .find(|list| *list == path)
+                .ok_or_else(|| {
+                    FrontendError::BadRequest(format!("Not an article list: {}", back))
+                })?,
+        };
+        let filter = serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+            .map_err(|_| FrontendError::BadRequest(format!("Not an article list: {}", back)))?
+            .into_iter()
+            .filter(|(key, _)| key != "cursor" && key != "outcomes")
+            .collect();
+        Ok(Self {
+            ids,
+            action,
+            back: path.to_string(),
+            filter,
+            cursor: field("cursor"),
+        })
+    }
+}
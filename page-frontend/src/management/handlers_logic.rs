@@ -1,17 +1,32 @@
 #![allow(clippy::all, unused_imports, dead_code)]
 
+use std::collections::HashMap;
+
 use axum::extract::Path;
+use axum::response::{IntoResponse, Response};
 use axum::{Extension, Form};
-use tonic::Request;
+use tonic::{Code, Request};
 
+use util_pb::batch_operation::Operation;
 use util_pb::create_request::Create;
 use util_pb::delete_request::Delete;
+use util_pb::query_request::Query;
+use util_pb::restore_request::Restore;
+use util_pb::transfer::{parse_minutes, ARTICLE_FIELDS, CATEGORY_FIELDS, NAME_FIELDS};
 use util_pb::update_request::Update;
-use util_pb::{Article, CreateRequest, DeleteRequest, UpdateRequest};
-
-use crate::common_handlers::{get_ids_from_tag_str, redirect_with_cookies, Redirect};
+use util_pb::{
+    Article, ArticleProjection, BatchOperation, BatchRequest, CreateRequest, DeleteRequest,
+    FieldMask, MergeTagsRequest, RestoreRequest, RestoreRevisionRequest, UpdateRequest,
+};
+
+use crate::common_handlers::{
+    find_tag_ids, get_ids_from_tag_str, query_string, redirect_with_cookies, Redirect, TeraHtml,
+};
 use crate::errors::{FrontendError, Result};
-use crate::management::forms::{FormArticle, FormCategory, FormTag};
+use crate::management::forms::{
+    ArticleFilter, BulkAction, BulkForm, FormArticle, FormCategory, FormTag, NameFilter,
+};
+use crate::management::handlers_pages::{page_article_conflict, BulkOutcome};
 use crate::shared_state::SharedState;
 
 /* =================================================================
@@ -22,8 +37,12 @@ articles
 
 ================================================================== */
 
-pub async fn handler_article_query() -> Result<Redirect> {
-    todo!()
+/// The filters posted to the query page, moved to its query string.
+pub async fn handler_article_query(Form(filter): Form<ArticleFilter>) -> Result<Redirect> {
+    Ok(redirect_with_cookies(
+        &format!("/management/articles/query?{}", query_string(&filter)?),
+        None,
+    ))
 }
 
 pub async fn handler_article_add(
@@ -42,6 +61,9 @@ pub async fn handler_article_add(
         updated_at: None,
         category_id: form_article.category_id,
         tags_id,
+        publish_at: parse_minutes(&form_article.publish_at),
+        expire_at: parse_minutes(&form_article.expire_at),
+        version: 0,
     };
 
     let req = Request::new(CreateRequest {
@@ -58,36 +80,181 @@ pub async fn handler_article_edit(
     Path(id): Path<i32>,
     Extension(state): Extension<SharedState>,
     Form(form_article): Form<FormArticle>,
-) -> Result<Redirect> {
+) -> Result<Response> {
+    let pending = form_article.clone();
     let tags_id = get_ids_from_tag_str(&form_article.tags, &state).await?;
+    let summary = form_article.saved_summary();
 
     let article = Article {
         id,
         title: form_article.title,
         content: form_article.content,
-        summary: form_article.summary,
+        summary,
         state: form_article.state,
         created_at: None,
         updated_at: None,
         category_id: form_article.category_id,
         tags_id,
+        publish_at: parse_minutes(&form_article.publish_at),
+        expire_at: parse_minutes(&form_article.expire_at),
+        version: form_article.version,
     };
+    // the form holds every field, so a cleared one is cleared in the database too
     let req = Request::new(UpdateRequest {
         update: Some(Update::Article(article)),
+        update_mask: Some(mask(&ARTICLE_FIELDS)),
     });
-    let res = state.client().update(req).await?.into_inner();
+    let res = match state.client().update(req).await {
+        Ok(res) => res.into_inner(),
+        Err(status) if status.code() == Code::Aborted => {
+            let page = page_article_conflict(id, &state, pending, status.message()).await?;
+            return Ok(page.into_response());
+        }
+        Err(status) => return Err(status.into()),
+    };
     Ok(redirect_with_cookies(
         &format!("/management/articles?msg=edit article with id {}", res.id),
         None,
+    )
+    .into_response())
+}
+
+/// The checked articles of an article list changed by one action in a single batch; then back
+/// to that list with its filters, each article marked with its outcome.
+pub async fn handler_article_bulk(
+    Extension(state): Extension<SharedState>,
+    Form(pairs): Form<Vec<(String, String)>>,
+) -> Result<Redirect> {
+    let form = BulkForm::try_from(pairs)?;
+    let tags_id = match &form.action {
+        BulkAction::AddTags(tags) => get_ids_from_tag_str(tags, &state).await?,
+        // only the tags there are, nothing is created to be removed
+        BulkAction::RemoveTags(tags) => find_tag_ids(tags, &state).await?.0,
+        _ => vec![],
+    };
+
+    // the current articles, for their tags and the version the change is made on
+    let articles = match form.ids.is_empty() {
+        true => vec![],
+        false => {
+            let query = util_pb::QueryRequest {
+                query: Some(Query::QueryArticle(util_pb::QueryArticle {
+                    ids: form.ids.clone(),
+                    projection: ArticleProjection::Summary as i32,
+                    ..Default::default()
+                })),
+            };
+            state.client().query(query).await?.into_inner().articles
+        }
+    };
+
+    let (mut outcomes, operations) = bulk_operations(&form, &articles, &tags_id);
+    if !operations.is_empty() {
+        let (ids, operations): (Vec<_>, Vec<_>) = operations.into_iter().unzip();
+        let req = Request::new(BatchRequest { operations });
+        let results = state.client().batch(req).await?.into_inner().results;
+        outcomes.extend(ids.into_iter().zip(results).map(|(id, res)| BulkOutcome {
+            id,
+            ok: res.code == Code::Ok as i32,
+            message: res.message,
+        }));
+    }
+    outcomes.sort_by_key(|outcome| form.ids.iter().position(|&id| id == outcome.id));
+
+    let outcomes = serde_json::to_string(&outcomes)
+        .map_err(|e| FrontendError::InternalError(e.to_string()))?;
+    let mut params = form
+        .filter
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    params.extend([("cursor", form.cursor.as_str()), ("outcomes", &outcomes)]);
+    Ok(redirect_with_cookies(
+        &format!("{}?{}", form.back, query_string(&params)?),
+        None,
     ))
 }
 
+/// The batch operations of a bulk action on `articles`, the current ones of the checked ids;
+/// the ids of none are skipped, with their outcome.
+fn bulk_operations(
+    form: &BulkForm,
+    articles: &[Article],
+    tags_id: &[i32],
+) -> (Vec<BulkOutcome>, Vec<(i32, BatchOperation)>) {
+    let update = |article: Article, fields: &[&str]| BatchOperation {
+        operation: Some(Operation::Update(UpdateRequest {
+            update: Some(Update::Article(article)),
+            update_mask: Some(mask(fields)),
+        })),
+    };
+
+    let mut skipped = vec![];
+    let mut operations = vec![];
+    for &id in &form.ids {
+        let Some(current) = articles.iter().find(|article| article.id == id) else {
+            skipped.push(BulkOutcome {
+                id,
+                ok: false,
+                message: "No such article, or it is in the trash.".to_string(),
+            });
+            continue;
+        };
+        let article = Article {
+            id,
+            version: current.version,
+            ..Article::default()
+        };
+        let operation = match &form.action {
+            BulkAction::State(state) => update(
+                Article {
+                    state: *state,
+                    ..article
+                },
+                &["state"],
+            ),
+            BulkAction::Category(category_id) => update(
+                Article {
+                    category_id: *category_id,
+                    ..article
+                },
+                &["category_id"],
+            ),
+            BulkAction::AddTags(_) | BulkAction::RemoveTags(_) => {
+                let mut tags = current.tags_id.clone();
+                if let BulkAction::AddTags(_) = form.action {
+                    let new_tags = tags_id.iter().filter(|id| !tags.contains(id));
+                    tags.extend(new_tags.collect::<Vec<_>>());
+                } else {
+                    tags.retain(|id| !tags_id.contains(id));
+                }
+                let article = Article {
+                    tags_id: tags,
+                    ..article
+                };
+                update(article, &["tags_id"])
+            }
+            BulkAction::Delete => BatchOperation {
+                operation: Some(Operation::Delete(DeleteRequest {
+                    delete: Some(Delete::ArticleId(id)),
+                    purge: false,
+                    reassign_to: None,
+                })),
+            },
+        };
+        operations.push((id, operation));
+    }
+    (skipped, operations)
+}
+
 pub async fn handler_article_delete(
     Path(id): Path<i32>,
     Extension(state): Extension<SharedState>,
 ) -> Result<Redirect> {
     let req = Request::new(DeleteRequest {
         delete: Some(Delete::ArticleId(id)),
+        purge: false,
+        reassign_to: None,
     });
     let res = state.client().delete(req).await?.into_inner();
     Ok(redirect_with_cookies(
@@ -96,6 +263,51 @@ pub async fn handler_article_delete(
     ))
 }
 
+pub async fn handler_article_restore(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(RestoreRequest {
+        restore: Some(Restore::ArticleId(id)),
+    });
+    let res = state.client().restore(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=restore article with id {}", res.id),
+        None,
+    ))
+}
+
+pub async fn handler_article_purge(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(DeleteRequest {
+        delete: Some(Delete::ArticleId(id)),
+        purge: true,
+        reassign_to: None,
+    });
+    let res = state.client().delete(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=purge article with id {}", res.id),
+        None,
+    ))
+}
+
+pub async fn handler_article_revision_restore(
+    Path((id, revision_id)): Path<(i32, i32)>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(RestoreRevisionRequest { revision_id });
+    let res = state.client().restore_revision(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!(
+            "/management/articles/edit/{}?msg=restore article {} to revision {}",
+            id, res.article_id, revision_id
+        ),
+        None,
+    ))
+}
+
 /* =================================================================
 
 
@@ -103,8 +315,11 @@ categories
 
 
 ================================================================== */
-pub async fn handler_category_query() -> Result<Redirect> {
-    todo!()
+pub async fn handler_category_query(Form(filter): Form<NameFilter>) -> Result<Redirect> {
+    Ok(redirect_with_cookies(
+        &format!("/management/categories/query?{}", query_string(&filter)?),
+        None,
+    ))
 }
 
 pub async fn handler_category_add(
@@ -114,6 +329,8 @@ pub async fn handler_category_add(
     let category = util_pb::Category {
         id: 0,
         name: form_category.name,
+        parent_id: form_category.parent_id.parse().ok(),
+        ..Default::default()
     };
 
     let req = Request::new(CreateRequest {
@@ -137,9 +354,12 @@ pub async fn handler_category_edit(
     let category = util_pb::Category {
         id,
         name: form_category.name,
+        parent_id: form_category.parent_id.parse().ok(),
+        ..Default::default()
     };
     let req = Request::new(UpdateRequest {
         update: Some(Update::Category(category)),
+        update_mask: Some(mask(&CATEGORY_FIELDS)),
     });
     let res = state.client().update(req).await?.into_inner();
     Ok(redirect_with_cookies(
@@ -157,6 +377,8 @@ pub async fn handler_category_delete(
 ) -> Result<Redirect> {
     let req = Request::new(DeleteRequest {
         delete: Some(Delete::CategoryId(id)),
+        purge: false,
+        reassign_to: None,
     });
     let res = state.client().delete(req).await?.into_inner();
     Ok(redirect_with_cookies(
@@ -167,6 +389,36 @@ pub async fn handler_category_delete(
         None,
     ))
 }
+
+pub async fn handler_category_restore(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(RestoreRequest {
+        restore: Some(Restore::CategoryId(id)),
+    });
+    let res = state.client().restore(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=restore category with id {}", res.id),
+        None,
+    ))
+}
+
+pub async fn handler_category_purge(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(DeleteRequest {
+        delete: Some(Delete::CategoryId(id)),
+        purge: true,
+        reassign_to: None,
+    });
+    let res = state.client().delete(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=purge category with id {}", res.id),
+        None,
+    ))
+}
 /* =================================================================
 
 
@@ -174,8 +426,11 @@ tags
 
 
 ================================================================== */
-pub async fn handler_tag_query() -> Result<Redirect> {
-    todo!()
+pub async fn handler_tag_query(Form(filter): Form<NameFilter>) -> Result<Redirect> {
+    Ok(redirect_with_cookies(
+        &format!("/management/tags/query?{}", query_string(&filter)?),
+        None,
+    ))
 }
 
 pub async fn handler_tag_add(
@@ -185,6 +440,7 @@ pub async fn handler_tag_add(
     let tag = util_pb::Tag {
         id: 0,
         name: form_tag.name,
+        ..Default::default()
     };
 
     let req = Request::new(CreateRequest {
@@ -205,9 +461,11 @@ pub async fn handler_tag_edit(
     let tag = util_pb::Tag {
         id,
         name: form_tag.name,
+        ..Default::default()
     };
     let req = Request::new(UpdateRequest {
         update: Some(Update::Tag(tag)),
+        update_mask: Some(mask(&NAME_FIELDS)),
     });
     let res = state.client().update(req).await?.into_inner();
     Ok(redirect_with_cookies(
@@ -222,6 +480,8 @@ pub async fn handler_tag_delete(
 ) -> Result<Redirect> {
     let req = Request::new(DeleteRequest {
         delete: Some(Delete::TagId(id)),
+        purge: false,
+        reassign_to: None,
     });
     let res = state.client().delete(req).await?.into_inner();
     Ok(redirect_with_cookies(
@@ -229,3 +489,220 @@ pub async fn handler_tag_delete(
         None,
     ))
 }
+
+pub async fn handler_tag_restore(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(RestoreRequest {
+        restore: Some(Restore::TagId(id)),
+    });
+    let res = state.client().restore(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=restore tag with id {}", res.id),
+        None,
+    ))
+}
+
+pub async fn handler_tag_purge(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<Redirect> {
+    let req = Request::new(DeleteRequest {
+        delete: Some(Delete::TagId(id)),
+        purge: true,
+        reassign_to: None,
+    });
+    let res = state.client().delete(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!("/management/trash?msg=purge tag with id {}", res.id),
+        None,
+    ))
+}
+
+/// Checked `source_ids` of the tag list merged into `target_id`; the keys repeat, so the form
+/// comes as pairs.
+pub async fn handler_tag_merge(
+    Extension(state): Extension<SharedState>,
+    Form(pairs): Form<Vec<(String, String)>>,
+) -> Result<Redirect> {
+    let mut source_ids = vec![];
+    let mut target_id = None;
+    for (key, value) in pairs {
+        let id = value
+            .parse::<i32>()
+            .map_err(|_| FrontendError::BadRequest(format!("Not a tag id: {}", value)))?;
+        match key.as_str() {
+            "source_ids" => source_ids.push(id),
+            "target_id" => target_id = Some(id),
+            _ => {}
+        }
+    }
+    let target_id =
+        target_id.ok_or_else(|| FrontendError::BadRequest("No target tag".to_string()))?;
+
+    let req = Request::new(MergeTagsRequest {
+        source_ids,
+        target_id,
+    });
+    let res = state.client().merge_tags(req).await?.into_inner();
+    Ok(redirect_with_cookies(
+        &format!(
+            "/management/tags?msg=merge into tag with id {}, re-tagging {} articles",
+            res.id, res.articles
+        ),
+        None,
+    ))
+}
+
+fn mask(fields: &[&str]) -> FieldMask {
+    FieldMask {
+        paths: fields.iter().map(|field| field.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn article(id: i32, tags_id: Vec<i32>, version: i32) -> Article {
+        Article {
+            id,
+            tags_id,
+            version,
+            ..Article::default()
+        }
+    }
+
+    fn updated(operation: &BatchOperation) -> &Article {
+        match &operation.operation {
+            Some(Operation::Update(UpdateRequest {
+                update: Some(Update::Article(article)),
+                ..
+            })) => article,
+            other => panic!("not an article update: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bulk_form_should_collect_the_ids() {
+        let form = BulkForm::try_from(pairs(&[
+            ("cursor", "abc"),
+            ("action", "state"),
+            ("state", "2"),
+            ("ids", "1000"),
+            ("ids", "1001"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            form,
+            BulkForm {
+                ids: vec![1000, 1001],
+                action: BulkAction::State(2),
+                back: "/management/articles".to_string(),
+                filter: vec![],
+                cursor: "abc".to_string(),
+            }
+        );
+
+        // the filters are read as a query string, without the page and outcomes of before
+        let form = BulkForm::try_from(pairs(&[
+            (
+                "back",
+                "/management/articles/query?tags=rust%2C%E6%96%87&cursor=x&outcomes=%5B%5D&",
+            ),
+            ("action", "delete"),
+        ]))
+        .unwrap();
+        assert_eq!(form.back, "/management/articles/query");
+        assert_eq!(
+            form.filter,
+            vec![("tags".to_string(), "rust,文".to_string())]
+        );
+    }
+
+    #[test]
+    fn bulk_form_should_reject_malformed_input() {
+        let cases = [
+            vec![("action", "delete"), ("ids", "x")],
+            vec![("action", "state"), ("state", "")],
+            vec![("action", "category"), ("category_id", "one")],
+            vec![("action", "rename"), ("ids", "1000")],
+            vec![("ids", "1000")],
+            vec![("action", "delete"), ("back", "https://example.com/?")],
+            vec![("action", "delete"), ("back", "/management/articlesX?")],
+            vec![
+                ("action", "delete"),
+                ("back", "/management/articles/../tags?"),
+            ],
+            vec![("action", "delete"), ("back", "/management/articles/文?")],
+        ];
+        for case in cases {
+            let res = BulkForm::try_from(pairs(&case));
+            assert!(
+                matches!(res, Err(FrontendError::BadRequest(_))),
+                "{:?}: {:?}",
+                case,
+                res
+            );
+        }
+    }
+
+    #[test]
+    fn bulk_operations_should_skip_missing_articles() {
+        let form = BulkForm {
+            ids: vec![1000, 7, 1001],
+            action: BulkAction::State(4),
+            back: String::new(),
+            filter: vec![],
+            cursor: String::new(),
+        };
+        let articles = vec![article(1001, vec![2], 5), article(1000, vec![1, 2], 3)];
+        let (skipped, operations) = bulk_operations(&form, &articles, &[]);
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!((skipped[0].id, skipped[0].ok), (7, false));
+        let changed = operations
+            .iter()
+            .map(|(id, operation)| (*id, updated(operation).state, updated(operation).version))
+            .collect::<Vec<_>>();
+        // each edit is made on the version fetched
+        assert_eq!(changed, vec![(1000, 4, 3), (1001, 4, 5)]);
+    }
+
+    #[test]
+    fn bulk_operations_should_change_the_tags() {
+        let articles = vec![article(1000, vec![1, 2], 3), article(1001, vec![2], 5)];
+        let tags_of = |action: BulkAction, tags_id: &[i32]| {
+            let form = BulkForm {
+                ids: vec![1000, 1001],
+                action,
+                back: String::new(),
+                filter: vec![],
+                cursor: String::new(),
+            };
+            let (skipped, operations) = bulk_operations(&form, &articles, tags_id);
+            assert!(skipped.is_empty());
+            operations
+                .iter()
+                .map(|(_, operation)| updated(operation).tags_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            tags_of(BulkAction::AddTags("a, b".to_string()), &[1, 3]),
+            vec![vec![1, 2, 3], vec![2, 1, 3]]
+        );
+        assert_eq!(
+            tags_of(BulkAction::RemoveTags("a".to_string()), &[2]),
+            vec![vec![1], vec![]]
+        );
+    }
+}
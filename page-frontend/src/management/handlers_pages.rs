@@ -1,14 +1,22 @@
 use std::collections::HashMap;
 
-use axum::extract::Path;
+use axum::extract::{self, Path};
 use axum::response::Html;
-use axum::Extension;
+use axum::{Extension, Form};
+use serde::{Deserialize, Serialize};
 use tera::Context;
 
 use util_pb::query_request::Query;
+use util_pb::transfer::{format_minutes, parse_minutes};
+use util_pb::{
+    Article, ArticleProjection, ArticleState, ArticleStateFilter, ListRevisionsRequest,
+    QueryResponse, TagMatch, Trash,
+};
 
 use crate::common_handlers::*;
 use crate::errors::{FrontendError, Result};
+use crate::management::diff::diff_lines;
+use crate::management::forms::{ArticleFilter, FormArticle, NameFilter};
 use crate::shared_state::SharedState;
 
 pub async fn page_dashboard(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
@@ -29,18 +37,92 @@ articles
 
 ================================================================== */
 
-pub async fn page_article_list(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
-    let mut ctx = Context::new();
+pub async fn page_article_list(
+    extract::Query(params): extract::Query<PageParams>,
+    extract::Query(outcomes): extract::Query<OutcomeParams>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    render_article_list(&state, params.cursor, &outcomes.parse()?).await
+}
 
-    let query_article = util_pb::QueryArticle::default();
-    let query = util_pb::QueryRequest {
-        query: Some(Query::QueryArticle(query_article)),
+/// What a bulk action did to one article.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BulkOutcome {
+    pub id: i32,
+    pub ok: bool,
+    pub message: String,
+}
+
+/// `?outcomes=` of the article lists a bulk action redirects to, the outcomes as JSON.
+#[derive(Debug, Default, Deserialize)]
+pub struct OutcomeParams {
+    #[serde(default)]
+    pub outcomes: String,
+}
+
+impl OutcomeParams {
+    pub fn parse(&self) -> Result<Vec<BulkOutcome>> {
+        if self.outcomes.is_empty() {
+            return Ok(vec![]);
+        }
+        serde_json::from_str(&self.outcomes)
+            .map_err(|e| FrontendError::BadRequest(format!("Not bulk outcomes: {}", e)))
+    }
+}
+
+/// A page of the article list, each article marked with its outcome of a bulk action if any.
+pub async fn render_article_list(
+    state: &SharedState,
+    cursor: String,
+    outcomes: &[BulkOutcome],
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+    ctx.insert("page_url", "/management/articles");
+    ctx.insert("filter_qs", "");
+
+    let query_article = util_pb::QueryArticle {
+        page_size: PAGE_SIZE,
+        projection: ArticleProjection::Summary as i32,
+        cursor,
+        ..Default::default()
     };
+    render_articles(
+        state,
+        Some(query_article),
+        ctx,
+        "management/articles/base.html",
+        outcomes,
+    )
+    .await
+}
 
-    let res = state.client().query(query).await?.into_inner();
+/// The articles of `query_article` in `template`, the article list or a page extending it; none
+/// without a query.
+async fn render_articles(
+    state: &SharedState,
+    query_article: Option<util_pb::QueryArticle>,
+    mut ctx: Context,
+    template: &str,
+    outcomes: &[BulkOutcome],
+) -> Result<TeraHtml> {
+    let res = match query_article {
+        Some(query_article) => {
+            ctx.insert("cursor", &query_article.cursor);
+            let query = util_pb::QueryRequest {
+                query: Some(Query::QueryArticle(query_article)),
+            };
+            state.client().query(query).await?.into_inner()
+        }
+        None => {
+            ctx.insert("cursor", "");
+            QueryResponse::default()
+        }
+    };
     let articles = res.articles;
+    ctx.insert("total", &res.total);
+    ctx.insert("next_cursor", &res.next_cursor);
 
-    let (c_map, t_map) = get_categories_tags(&state).await;
+    let (c_map, t_map) = get_categories_tags(state).await;
 
     let categories = articles
         .iter()
@@ -66,19 +148,85 @@ pub async fn page_article_list(Extension(state): Extension<SharedState>) -> Resu
         .collect::<Vec<_>>();
     ctx.insert("articles_with_name", &articles_with_name);
 
-    let page = state
-        .tera()
-        .render("management/articles/base.html", &ctx)
-        .map_err(|err| {
-            tracing::error!("render error: \n{:?}", err);
-            tracing::error!("ctx: {:?}", ctx);
-            <tera::Error as Into<FrontendError>>::into(err)
-        })?;
+    let row_outcomes = articles
+        .iter()
+        .map(|article| outcomes.iter().find(|outcome| outcome.id == article.id))
+        .collect::<Vec<_>>();
+    ctx.insert("row_outcomes", &row_outcomes);
+    ctx.insert("outcomes", outcomes);
+    ctx.insert(
+        "failures",
+        &outcomes.iter().filter(|outcome| !outcome.ok).count(),
+    );
+
+    // for the bulk action bar
+    ctx.insert("categories", &category_tree(get_categories(state).await));
+    ctx.insert("article_states", &articles_states());
+
+    let page = state.tera().render(template, &ctx).map_err(|err| {
+        tracing::error!("render error: \n{:?}", err);
+        tracing::error!("ctx: {:?}", ctx);
+        <tera::Error as Into<FrontendError>>::into(err)
+    })?;
     Ok(Html(page))
 }
 
-pub async fn page_article_query() -> Result<TeraHtml> {
-    todo!()
+/// The article list narrowed by the filters in the query string.
+pub async fn page_article_query(
+    extract::Query(filter): extract::Query<ArticleFilter>,
+    extract::Query(params): extract::Query<PageParams>,
+    extract::Query(outcomes): extract::Query<OutcomeParams>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+    ctx.insert("page_url", "/management/articles/query");
+    let filter_qs = query_string(&filter)?;
+    ctx.insert(
+        "filter_qs",
+        &match filter_qs.is_empty() {
+            true => filter_qs,
+            false => filter_qs + "&",
+        },
+    );
+
+    // tags are looked up, not created by a search
+    let (tags_id, unknown_tags) = find_tag_ids(&filter.tags, &state).await?;
+    let tag_match = match filter.tag_match.as_str() {
+        "any" => TagMatch::Any,
+        "none" => TagMatch::None,
+        _ => TagMatch::All,
+    };
+    // no article carries a tag there is not, so leaving it out would only widen the results
+    let no_match = !unknown_tags.is_empty()
+        && (tag_match == TagMatch::All || (tag_match == TagMatch::Any && tags_id.is_empty()));
+    ctx.insert("unknown_tags", &unknown_tags);
+    ctx.insert("no_match", &no_match);
+    let states = filter.state.parse().into_iter().collect();
+    let query_article = util_pb::QueryArticle {
+        title: filter.title.trim().to_string(),
+        state_filter: Some(ArticleStateFilter { states }),
+        category_id: filter.category_id.parse().unwrap_or_default(),
+        include_descendants: true,
+        tags_id,
+        tag_match: tag_match as i32,
+        from_date: filter.from_date.clone(),
+        to_date: filter.to_date.clone(),
+        timezone: state.timezone().to_string(),
+        page_size: PAGE_SIZE,
+        projection: ArticleProjection::Summary as i32,
+        cursor: params.cursor,
+        ..Default::default()
+    };
+    ctx.insert("filter", &filter);
+
+    render_articles(
+        &state,
+        (!no_match).then_some(query_article),
+        ctx,
+        "management/articles/query.html",
+        &outcomes.parse()?,
+    )
+    .await
 }
 
 pub async fn page_article_add(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
@@ -105,13 +253,6 @@ pub async fn page_article_edit(
     Path(id): Path<i32>,
     Extension(state): Extension<SharedState>,
 ) -> Result<TeraHtml> {
-    let mut ctx = Context::new();
-
-    let categories = get_categories(&state).await;
-    let article_states = articles_states();
-    ctx.insert("article_states", &article_states);
-    ctx.insert("categories", &categories);
-
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryArticle(util_pb::QueryArticle {
             ids: vec![id],
@@ -120,7 +261,6 @@ pub async fn page_article_edit(
     };
     let mut res = state.client().query(query).await?.into_inner();
     let article = res.articles.pop().unwrap();
-    ctx.insert("article", &article);
 
     let (_, t_map) = get_categories_tags(&state).await;
     let tags_name = article
@@ -129,7 +269,98 @@ pub async fn page_article_edit(
         .map(|tag_id| t_map.get(tag_id).unwrap().clone())
         .collect::<Vec<_>>()
         .join(", ");
-    ctx.insert("tags_name", &tags_name);
+
+    render_article_edit(&state, &article, &tags_name, None).await
+}
+
+/// The edit form again, filled with the pending edit carried back from the review page.
+pub async fn page_article_edit_pending(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+    Form(form_article): Form<FormArticle>,
+) -> Result<TeraHtml> {
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            ids: vec![id],
+            projection: ArticleProjection::Summary as i32,
+            ..Default::default()
+        })),
+    };
+    let mut res = state.client().query(query).await?.into_inner();
+    let current = res
+        .articles
+        .pop()
+        .ok_or_else(|| FrontendError::NotFound(format!("No such article: {}", id)))?;
+    let article = Article {
+        title: form_article.title,
+        content: form_article.content,
+        summary: form_article.summary,
+        state: form_article.state,
+        category_id: form_article.category_id,
+        publish_at: parse_minutes(&form_article.publish_at),
+        expire_at: parse_minutes(&form_article.expire_at),
+        version: form_article.version,
+        ..current
+    };
+    render_article_edit(&state, &article, &form_article.tags, None).await
+}
+
+/// The edit form again after a save lost to a newer edit: the pending edit is kept and the
+/// current article shown next to it, saving again overwrites the current one.
+pub async fn page_article_conflict(
+    id: i32,
+    state: &SharedState,
+    form_article: FormArticle,
+    message: &str,
+) -> Result<TeraHtml> {
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            ids: vec![id],
+            ..Default::default()
+        })),
+    };
+    let mut res = state.client().query(query).await?.into_inner();
+    let current = res
+        .articles
+        .pop()
+        .ok_or_else(|| FrontendError::NotFound(format!("No such article: {}", id)))?;
+    let article = Article {
+        title: form_article.title,
+        content: form_article.content,
+        summary: form_article.summary,
+        state: form_article.state,
+        category_id: form_article.category_id,
+        publish_at: parse_minutes(&form_article.publish_at),
+        expire_at: parse_minutes(&form_article.expire_at),
+        ..current.clone()
+    };
+    render_article_edit(
+        state,
+        &article,
+        &form_article.tags,
+        Some((message, &current)),
+    )
+    .await
+}
+
+async fn render_article_edit(
+    state: &SharedState,
+    article: &Article,
+    tags_name: &str,
+    conflict: Option<(&str, &Article)>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+    if let Some((message, current)) = conflict {
+        ctx.insert("conflict", message);
+        ctx.insert("current", current);
+    }
+
+    let categories = get_categories(state).await;
+    let article_states = articles_states();
+    ctx.insert("article_states", &article_states);
+    ctx.insert("categories", &categories);
+    ctx.insert("article", article);
+    ctx.insert("tags_name", tags_name);
 
     let page = state
         .tera()
@@ -142,6 +373,208 @@ pub async fn page_article_edit(
     Ok(Html(page))
 }
 
+/// Diff of the stored article against the submitted edit, before anything is saved.
+pub async fn page_article_review(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+    Form(form_article): Form<FormArticle>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            ids: vec![id],
+            ..Default::default()
+        })),
+    };
+    let mut res = state.client().query(query).await?.into_inner();
+    let article = res
+        .articles
+        .pop()
+        .ok_or_else(|| FrontendError::NotFound(format!("No such article: {}", id)))?;
+
+    let (c_map, t_map) = get_categories_tags(&state).await;
+    let state_name = |state: i32| {
+        ArticleState::from_i32(state)
+            .map(|state| state.as_str_name().to_string())
+            .unwrap_or_default()
+    };
+    let old_tags = article
+        .tags_id
+        .iter()
+        .filter_map(|tag_id| t_map.get(tag_id).cloned())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let new_tags = split_tags(&form_article.tags).join(", ");
+
+    let changes = [
+        ("Title", article.title.clone(), form_article.title.clone()),
+        (
+            "State",
+            state_name(article.state),
+            state_name(form_article.state),
+        ),
+        (
+            "Category",
+            c_map.get(&article.category_id).cloned().unwrap_or_default(),
+            c_map
+                .get(&form_article.category_id)
+                .cloned()
+                .unwrap_or_default(),
+        ),
+        ("Tags", old_tags, new_tags),
+        (
+            "Publish at",
+            article
+                .publish_at
+                .as_ref()
+                .map(format_minutes)
+                .unwrap_or_default(),
+            pending_minutes(&form_article.publish_at),
+        ),
+        (
+            "Expire at",
+            article
+                .expire_at
+                .as_ref()
+                .map(format_minutes)
+                .unwrap_or_default(),
+            pending_minutes(&form_article.expire_at),
+        ),
+        (
+            "Summary",
+            article.summary.clone(),
+            form_article.saved_summary(),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .collect::<Vec<_>>();
+    ctx.insert("changes", &changes);
+
+    let content_changed = article.content != form_article.content;
+    ctx.insert("content_changed", &content_changed);
+    ctx.insert("diff", &diff_lines(&article.content, &form_article.content));
+    ctx.insert("id", &id);
+    ctx.insert("article", &form_article);
+
+    let page = state
+        .tera()
+        .render("management/articles/review.html", &ctx)
+        .map_err(|err| {
+            tracing::error!("render error: {:?}", err);
+            <tera::Error as Into<FrontendError>>::into(err)
+        })?;
+    Ok(Html(page))
+}
+
+pub async fn page_article_revisions(
+    Path(id): Path<i32>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            ids: vec![id],
+            projection: ArticleProjection::Summary as i32,
+            ..Default::default()
+        })),
+    };
+    let mut res = state.client().query(query).await?.into_inner();
+    let article = res
+        .articles
+        .pop()
+        .ok_or_else(|| FrontendError::NotFound(format!("No such article: {}", id)))?;
+    ctx.insert("article", &article);
+
+    let req = ListRevisionsRequest { article_id: id };
+    let revisions = state
+        .client()
+        .list_revisions(req)
+        .await?
+        .into_inner()
+        .revisions;
+
+    let (c_map, t_map) = get_categories_tags(&state).await;
+    let revisions_with_name = revisions
+        .iter()
+        .map(|revision| {
+            let category = c_map
+                .get(&revision.category_id)
+                .cloned()
+                .unwrap_or_default();
+            let tags = revision
+                .tags_id
+                .iter()
+                .filter_map(|tag_id| t_map.get(tag_id).cloned())
+                .collect::<Vec<_>>()
+                .join(", ");
+            (revision, category, tags)
+        })
+        .collect::<Vec<_>>();
+    ctx.insert("revisions_with_name", &revisions_with_name);
+
+    let page = state
+        .tera()
+        .render("management/articles/revisions.html", &ctx)
+        .map_err(|err| {
+            tracing::error!("render error: {:?}", err);
+            <tera::Error as Into<FrontendError>>::into(err)
+        })?;
+    Ok(Html(page))
+}
+
+pub async fn page_article_revision(
+    Path((id, revision_id)): Path<(i32, i32)>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let req = ListRevisionsRequest { article_id: id };
+    let revision = state
+        .client()
+        .list_revisions(req)
+        .await?
+        .into_inner()
+        .revisions
+        .into_iter()
+        .find(|revision| revision.id == revision_id)
+        .ok_or_else(|| FrontendError::NotFound(format!("No such revision: {}", revision_id)))?;
+    ctx.insert("revision", &revision);
+
+    let (c_map, t_map) = get_categories_tags(&state).await;
+    let category = c_map
+        .get(&revision.category_id)
+        .cloned()
+        .unwrap_or_default();
+    let tags = revision
+        .tags_id
+        .iter()
+        .filter_map(|tag_id| t_map.get(tag_id).cloned())
+        .collect::<Vec<_>>()
+        .join(", ");
+    ctx.insert("category", &category);
+    ctx.insert("tags", &tags);
+
+    let page = state
+        .tera()
+        .render("management/articles/revision.html", &ctx)
+        .map_err(|err| {
+            tracing::error!("render error: {:?}", err);
+            <tera::Error as Into<FrontendError>>::into(err)
+        })?;
+    Ok(Html(page))
+}
+
+/// A time of the review page as the edit saves it, which clears it for an empty input.
+fn pending_minutes(input: &str) -> String {
+    parse_minutes(input)
+        .as_ref()
+        .map(format_minutes)
+        .unwrap_or_default()
+}
+
 /* =================================================================
 
 
@@ -152,7 +585,7 @@ categories
 pub async fn page_category_list(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
     let mut ctx = Context::new();
 
-    let categories = get_categories(&state).await;
+    let categories = category_tree(get_categories(&state).await);
     ctx.insert("categories", &categories);
 
     let page = state
@@ -163,12 +596,34 @@ pub async fn page_category_list(Extension(state): Extension<SharedState>) -> Res
     Ok(Html(page))
 }
 
-pub async fn page_category_query(Extension(_state): Extension<SharedState>) -> Result<TeraHtml> {
-    todo!()
+/// The categories whose name contains the one in the query string, in their tree.
+pub async fn page_category_query(
+    extract::Query(filter): extract::Query<NameFilter>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryCategory(util_pb::QueryCategory {
+            name: filter.name.trim().to_string(),
+            ..Default::default()
+        })),
+    };
+    let categories = state.client().query(query).await?.into_inner().categories;
+    ctx.insert("categories", &category_tree(categories));
+    ctx.insert("filter", &filter);
+
+    let page = state
+        .tera()
+        .render("management/categories/query.html", &ctx)?;
+    Ok(Html(page))
 }
 
 pub async fn page_category_add(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
-    let ctx = Context::new();
+    let mut ctx = Context::new();
+
+    let categories = category_tree(get_categories(&state).await);
+    ctx.insert("categories", &categories);
 
     let page = state
         .tera()
@@ -200,6 +655,9 @@ pub async fn page_category_edit(
         .unwrap();
     ctx.insert("category", &category);
 
+    let categories = category_tree(get_categories(&state).await);
+    ctx.insert("categories", &categories);
+
     let page = state
         .tera()
         .render("management/categories/edit.html", &ctx)
@@ -228,8 +686,25 @@ pub async fn page_tag_list(Extension(state): Extension<SharedState>) -> Result<T
     Ok(Html(page))
 }
 
-pub async fn page_tag_query() -> Result<TeraHtml> {
-    todo!()
+/// The tags whose name contains the one in the query string.
+pub async fn page_tag_query(
+    extract::Query(filter): extract::Query<NameFilter>,
+    Extension(state): Extension<SharedState>,
+) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryTag(util_pb::QueryTag {
+            name: filter.name.trim().to_string(),
+            ..Default::default()
+        })),
+    };
+    let tags = state.client().query(query).await?.into_inner().tags;
+    ctx.insert("tags", &tags);
+    ctx.insert("filter", &filter);
+
+    let page = state.tera().render("management/tags/query.html", &ctx)?;
+    Ok(Html(page))
 }
 
 pub async fn page_tag_add(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
@@ -274,13 +749,64 @@ pub async fn page_tag_edit(
 /* =================================================================
 
 
+trash
+
+
+================================================================== */
+pub async fn page_trash(Extension(state): Extension<SharedState>) -> Result<TeraHtml> {
+    let mut ctx = Context::new();
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryArticle(util_pb::QueryArticle {
+            projection: ArticleProjection::Summary as i32,
+            trash: Trash::Only as i32,
+            ..Default::default()
+        })),
+    };
+    let articles = state.client().query(query).await?.into_inner().articles;
+    ctx.insert("articles", &articles);
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryCategory(util_pb::QueryCategory {
+            trash: Trash::Only as i32,
+            ..Default::default()
+        })),
+    };
+    let categories = state.client().query(query).await?.into_inner().categories;
+    ctx.insert("categories", &categories);
+
+    let query = util_pb::QueryRequest {
+        query: Some(Query::QueryTag(util_pb::QueryTag {
+            trash: Trash::Only as i32,
+            ..Default::default()
+        })),
+    };
+    let tags = state.client().query(query).await?.into_inner().tags;
+    ctx.insert("tags", &tags);
+
+    let page = state
+        .tera()
+        .render("management/trash.html", &ctx)
+        .map_err(|err| {
+            tracing::error!("render error: {:?}", err);
+            <tera::Error as Into<FrontendError>>::into(err)
+        })?;
+    Ok(Html(page))
+}
+
+/* =================================================================
+
+
 utils
 
 
 ================================================================== */
 async fn get_categories_tags(state: &SharedState) -> (HashMap<i32, String>, HashMap<i32, String>) {
-    // categories
-    let query_category = util_pb::QueryCategory::default();
+    // categories, those in the trash bin included to name their articles
+    let query_category = util_pb::QueryCategory {
+        trash: Trash::Include as i32,
+        ..Default::default()
+    };
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryCategory(query_category)),
     };
@@ -293,7 +819,10 @@ async fn get_categories_tags(state: &SharedState) -> (HashMap<i32, String>, Hash
     }
 
     // tags
-    let query_tag = util_pb::QueryTag::default();
+    let query_tag = util_pb::QueryTag {
+        trash: Trash::Include as i32,
+        ..Default::default()
+    };
     let query = util_pb::QueryRequest {
         query: Some(Query::QueryTag(query_tag)),
     };
@@ -307,3 +836,16 @@ async fn get_categories_tags(state: &SharedState) -> (HashMap<i32, String>, Hash
 
     (c_map, t_map)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_minutes_should_show_what_is_saved() {
+        assert_eq!(pending_minutes("2026-10-18T09:30"), "2026-10-18 09:30");
+        // an emptied box clears the time rather than keeping the stored one
+        assert_eq!(pending_minutes(""), "");
+        assert_eq!(pending_minutes(" "), "");
+    }
+}
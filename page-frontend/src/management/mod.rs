@@ -1,3 +1,4 @@
+mod diff;
 mod forms;
 mod handlers_logic;
 mod handlers_pages;
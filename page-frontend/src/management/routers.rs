@@ -1,4 +1,4 @@
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::Router;
 
 use crate::management::handlers_logic::*;
@@ -16,7 +16,18 @@ pub fn management_router() -> Router {
             "/edit/:id",
             get(page_article_edit).post(handler_article_edit),
         )
-        .route("/delete/:id", get(handler_article_delete));
+        .route("/edit/:id/review", post(page_article_review))
+        .route("/edit/:id/back", post(page_article_edit_pending))
+        .route("/bulk", post(handler_article_bulk))
+        .route("/delete/:id", post(handler_article_delete))
+        .route("/restore/:id", post(handler_article_restore))
+        .route("/purge/:id", post(handler_article_purge))
+        .route("/:id/revisions", get(page_article_revisions))
+        .route("/:id/revisions/:revision_id", get(page_article_revision))
+        .route(
+            "/:id/revisions/:revision_id/restore",
+            post(handler_article_revision_restore),
+        );
 
     let category_router = Router::new()
         .route("/", get(page_category_list))
@@ -29,17 +40,23 @@ pub fn management_router() -> Router {
             "/edit/:id",
             get(page_category_edit).post(handler_category_edit),
         )
-        .route("/delete/:id", get(handler_category_delete));
+        .route("/delete/:id", post(handler_category_delete))
+        .route("/restore/:id", post(handler_category_restore))
+        .route("/purge/:id", post(handler_category_purge));
 
     let tag_router = Router::new()
         .route("/", get(page_tag_list))
         .route("/query", get(page_tag_query).post(handler_tag_query))
         .route("/add", get(page_tag_add).post(handler_tag_add))
         .route("/edit/:id", get(page_tag_edit).post(handler_tag_edit))
-        .route("/delete/:id", get(handler_tag_delete));
+        .route("/delete/:id", post(handler_tag_delete))
+        .route("/restore/:id", post(handler_tag_restore))
+        .route("/purge/:id", post(handler_tag_purge))
+        .route("/merge", post(handler_tag_merge));
 
     Router::new()
         .route("/", get(page_dashboard))
+        .route("/trash", get(page_trash))
         .nest("/articles", article_router)
         .nest("/categories", category_router)
         .nest("/tags", tag_router)
@@ -20,12 +20,17 @@ impl Clone for SharedState {
 struct InnerState {
     tera: Tera,
     client: BlogServiceClient<Channel>,
+    timezone: String,
 }
 
 impl SharedState {
-    pub fn new(tera: Tera, client: BlogServiceClient<Channel>) -> Self {
+    pub fn new(tera: Tera, client: BlogServiceClient<Channel>, timezone: String) -> Self {
         Self {
-            inner_state: Arc::new(InnerState { tera, client }),
+            inner_state: Arc::new(InnerState {
+                tera,
+                client,
+                timezone,
+            }),
         }
     }
 
@@ -36,4 +41,9 @@ impl SharedState {
     pub fn tera(&self) -> &Tera {
         &self.inner_state.tera
     }
+
+    /// IANA name of the timezone the public pages count calendar months in.
+    pub fn timezone(&self) -> &str {
+        &self.inner_state.timezone
+    }
 }
@@ -1,17 +1,35 @@
+use std::time::Duration;
+
 use sqlx::PgPool;
 
-use svc_backend::{BackendInnerService, DBPool};
+use svc_backend::{BackendError, BackendInnerService, DBPool, Publisher};
 use util_pb::blog_service_server::BlogServiceServer;
 
+/// Re-index every article before serving, once after upgrading a database written before the
+/// search index existed; writes keep the index up to date afterwards.
+const REBUILD_SEARCH_INDEX: &str = "--rebuild-search-index";
+
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<(), BackendError> {
     dotenv::dotenv().ok();
 
     tracing_subscriber::fmt().pretty().init();
 
     let db_addr = std::env::var("DATABASE_URL").unwrap();
-    let pg_pool = PgPool::connect(&db_addr).await.unwrap();
-    let inner_svc = BackendInnerService::new(DBPool::new(pg_pool));
+    let pg_pool = PgPool::connect(&db_addr).await?;
+    let db_pool = DBPool::new(pg_pool);
+    if std::env::args().any(|arg| arg == REBUILD_SEARCH_INDEX) {
+        tracing::info!("Rebuilding the search index");
+        db_pool.rebuild_search_index().await?;
+    }
+
+    let publish_interval = std::env::var("PUBLISH_INTERVAL_SECS")
+        .map(|secs| secs.parse().unwrap())
+        .unwrap_or(60);
+    let publisher = Publisher::new(db_pool.clone());
+    tokio::spawn(publisher.run(Duration::from_secs(publish_interval)));
+
+    let inner_svc = BackendInnerService::new(db_pool);
 
     let addr = std::env::var("BACKEND_URL").unwrap();
     let svc = BlogServiceServer::new(inner_svc);
@@ -22,4 +40,5 @@ async fn main() {
         .serve(addr.parse().unwrap())
         .await
         .unwrap();
+    Ok(())
 }
@@ -9,6 +9,10 @@ pub enum BackendError {
 
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+
+    /// The write was made on a version of the row that is not the current one anymore.
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 impl From<BackendError> for tonic::Status {
@@ -16,6 +20,7 @@ impl From<BackendError> for tonic::Status {
         match e {
             BackendError::SqlxError(e) => tonic::Status::internal(e.to_string()),
             BackendError::InvalidRequest(e) => tonic::Status::invalid_argument(e),
+            BackendError::Conflict(e) => tonic::Status::aborted(e),
         }
     }
 }
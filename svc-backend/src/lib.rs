@@ -1,8 +1,11 @@
 pub use error::BackendError;
 use error::Result;
+pub use publisher::Publisher;
 pub use service::BackendInnerService;
-pub use storage::DBPool;
+pub use storage::{BlogDB, DBPool, MemoryDB};
 
 mod error;
+mod publisher;
+mod search;
 mod service;
 mod storage;
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use crate::error::Result;
+use crate::storage::{BlogDB, DBPool, ID};
+
+#[cfg(test)]
+mod tests;
+
+/// Source of the current time, so that tests can drive the publisher.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Articles changed by one run of the publisher.
+#[derive(Debug, Default, PartialEq)]
+pub struct PublishReport {
+    pub published: Vec<ID>,
+    pub expired: Vec<ID>,
+}
+
+/// Promotes due scheduled articles to published, and hides expired ones, in a `BlogDB`,
+/// Postgres unless told otherwise.
+pub struct Publisher<DB = DBPool, C: Clock = SystemClock> {
+    db: DB,
+    clock: C,
+}
+
+impl<DB: BlogDB> Publisher<DB> {
+    pub fn new(db: DB) -> Self {
+        Self::with_clock(db, SystemClock)
+    }
+}
+
+impl<DB: BlogDB, C: Clock> Publisher<DB, C> {
+    pub fn with_clock(db: DB, clock: C) -> Self {
+        Self { db, clock }
+    }
+
+    pub async fn run_once(&self) -> Result<PublishReport> {
+        let now = self.clock.now();
+        let published = self.db.publish_due(now).await?;
+        let expired = self.db.expire_due(now).await?;
+        Ok(PublishReport { published, expired })
+    }
+
+    /// Run every `interval` forever; a failed run is logged and retried at the next tick.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match self.run_once().await {
+                Ok(report) if report != PublishReport::default() => {
+                    tracing::info!("publisher: {:?}", report);
+                }
+                Ok(_) => {}
+                Err(err) => tracing::error!("publisher error: {}", err),
+            }
+        }
+    }
+}
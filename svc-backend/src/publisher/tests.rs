@@ -0,0 +1,141 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Local, TimeZone};
+use sqlx_db_tester::TestPg;
+
+use util_pb::{to_timestamp, Article, ArticleState, QueryArticle};
+
+use crate::publisher::{Clock, PublishReport, Publisher};
+use crate::storage::{BlogDB, DBPool, MemoryDB};
+
+async fn load_test_db() -> TestPg {
+    dotenv::dotenv().ok();
+
+    TestPg::new(
+        std::env::var("TDB_URL").unwrap(),
+        Path::new("../migrations"),
+    )
+}
+
+struct FakeClock(Mutex<DateTime<Local>>);
+
+impl FakeClock {
+    fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for &FakeClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.0.lock().unwrap()
+    }
+}
+
+async fn state_of<DB: BlogDB>(db: &DB, id: i32) -> ArticleState {
+    let req = QueryArticle {
+        ids: vec![id],
+        ..QueryArticle::default()
+    };
+    let article = db.query_articles(req).await.unwrap().pop().unwrap();
+    ArticleState::from_i32(article.state).unwrap()
+}
+
+#[tokio::test]
+async fn publisher_should_follow_the_clock() {
+    let tdb = load_test_db().await;
+    let pool = tdb.get_pool().await;
+    publisher_with_fake_clock(DBPool::new(pool)).await;
+}
+
+#[tokio::test]
+async fn publisher_should_follow_the_clock_in_memory() {
+    publisher_with_fake_clock(MemoryDB::sample()).await;
+}
+
+async fn publisher_with_fake_clock<DB: BlogDB + Clone>(db: DB) {
+    let start = Local.with_ymd_and_hms(2030, 1, 1, 8, 0, 0).unwrap();
+    let clock = FakeClock(Mutex::new(start));
+    let publisher = Publisher::with_clock(db.clone(), &clock);
+
+    let req = Article {
+        title: "scheduled".to_string(),
+        content: "scheduled".to_string(),
+        state: ArticleState::Scheduled as i32,
+        category_id: 1,
+        publish_at: Some(to_timestamp(start + Duration::hours(1))),
+        expire_at: Some(to_timestamp(start + Duration::hours(3))),
+        ..Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+
+    // not due yet
+    assert_eq!(
+        publisher.run_once().await.unwrap(),
+        PublishReport::default()
+    );
+    assert_eq!(state_of(&db, id).await, ArticleState::Scheduled);
+
+    clock.advance(Duration::hours(1));
+    let report = publisher.run_once().await.unwrap();
+    assert_eq!(report.published, vec![id]);
+    assert!(report.expired.is_empty());
+    assert_eq!(state_of(&db, id).await, ArticleState::Published);
+
+    clock.advance(Duration::hours(1));
+    assert_eq!(
+        publisher.run_once().await.unwrap(),
+        PublishReport::default()
+    );
+
+    clock.advance(Duration::hours(1));
+    let report = publisher.run_once().await.unwrap();
+    assert!(report.published.is_empty());
+    assert_eq!(report.expired, vec![id]);
+    assert_eq!(state_of(&db, id).await, ArticleState::Hidden);
+
+    // the expiry is spent, publishing again by hand sticks
+    let req = Article {
+        id,
+        state: ArticleState::Published as i32,
+        ..Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+    clock.advance(Duration::days(1));
+    assert_eq!(
+        publisher.run_once().await.unwrap(),
+        PublishReport::default()
+    );
+    assert_eq!(state_of(&db, id).await, ArticleState::Published);
+}
+
+#[tokio::test]
+async fn scheduled_article_should_need_publish_at() {
+    let tdb = load_test_db().await;
+    let pool = tdb.get_pool().await;
+    scheduled_without_publish_at(DBPool::new(pool)).await;
+}
+
+#[tokio::test]
+async fn scheduled_article_should_need_publish_at_in_memory() {
+    scheduled_without_publish_at(MemoryDB::sample()).await;
+}
+
+async fn scheduled_without_publish_at<DB: BlogDB>(db: DB) {
+    let req = Article {
+        title: "scheduled".to_string(),
+        content: "scheduled".to_string(),
+        state: ArticleState::Scheduled as i32,
+        category_id: 1,
+        ..Article::default()
+    };
+    assert!(db.add_article(req).await.is_err());
+
+    let req = Article {
+        id: 1000,
+        state: ArticleState::Scheduled as i32,
+        ..Article::default()
+    };
+    assert!(db.edit_article(req, None).await.is_err());
+    assert_eq!(state_of(&db, 1000).await, ArticleState::Published);
+}
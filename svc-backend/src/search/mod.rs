@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use jieba_rs::Jieba;
+use util_pb::{QueryArticle, SearchHit, SearchRequest, SearchResponse};
+
+use crate::error::Result;
+use crate::storage::{BlogDB, ID};
+
+#[cfg(test)]
+mod tests;
+
+pub const DEFAULT_PAGE_SIZE: i32 = 10;
+pub const TAG_WEIGHT: f32 = 2.0;
+
+const TITLE_WEIGHT: f32 = 3.0;
+const SUMMARY_WEIGHT: f32 = 2.0;
+const CONTENT_WEIGHT: f32 = 1.0;
+
+const MAX_TERM_LEN: usize = 255;
+const SNIPPET_BEFORE: usize = 30;
+const SNIPPET_LEN: usize = 120;
+
+fn jieba() -> &'static Jieba {
+    static JIEBA: OnceLock<Jieba> = OnceLock::new();
+    JIEBA.get_or_init(Jieba::new)
+}
+
+/// Lowercased terms of `text`, Chinese segmented by jieba in search mode.
+pub fn tokenize(text: &str) -> Vec<String> {
+    jieba()
+        .cut_for_search(text, true)
+        .into_iter()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| word.trim().to_lowercase())
+        .filter(|term| term.len() <= MAX_TERM_LEN)
+        .collect()
+}
+
+/// Distinct terms of a search query, in order of appearance.
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokenize(query)
+        .into_iter()
+        .filter(|term| seen.insert(term.clone()))
+        .collect()
+}
+
+/// Weighted term frequencies of an article; a term in the title counts more than in the content.
+pub fn term_weights(title: &str, summary: &str, content: &str) -> HashMap<String, f32> {
+    let mut weights = HashMap::new();
+    for (text, weight) in [
+        (title, TITLE_WEIGHT),
+        (summary, SUMMARY_WEIGHT),
+        (content, CONTENT_WEIGHT),
+    ] {
+        for term in tokenize(text) {
+            *weights.entry(term).or_insert(0.0) += weight;
+        }
+    }
+    weights
+}
+
+/// Scores of the articles in `matches` (article id, query term, weight), best first.
+///
+/// Each match counts `weight * idf`, and the sum is scaled by the share of `terms` the article matched.
+pub fn rank(
+    terms: &[String],
+    matches: &[(ID, String, f32)],
+    total_articles: i64,
+) -> Vec<(ID, f32)> {
+    let mut df = HashMap::<&str, HashSet<ID>>::new();
+    for (id, term, _) in matches {
+        df.entry(term).or_default().insert(*id);
+    }
+
+    let mut scores = HashMap::<ID, (f32, HashSet<&str>)>::new();
+    for (id, term, weight) in matches {
+        let idf = (1.0 + total_articles.max(1) as f32 / df[term.as_str()].len() as f32).ln();
+        let score = scores.entry(*id).or_default();
+        score.0 += weight * idf;
+        score.1.insert(term);
+    }
+
+    let mut ranked = scores
+        .into_iter()
+        .map(|(id, (score, matched))| {
+            let coverage = matched.len() as f32 / terms.len().max(1) as f32;
+            (id, score * coverage)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+    ranked
+}
+
+/// The page of `req` out of the `matches` of its `terms`, the articles of the page fetched from `db`.
+pub async fn hits<DB: BlogDB + ?Sized>(
+    db: &DB,
+    req: &SearchRequest,
+    terms: &[String],
+    matches: &[(ID, String, f32)],
+    total_articles: i64,
+) -> Result<SearchResponse> {
+    let ranked = rank(terms, matches, total_articles);
+    let total = ranked.len() as i64;
+    let page_size = if req.page_size > 0 {
+        req.page_size
+    } else {
+        DEFAULT_PAGE_SIZE
+    } as usize;
+    let page = ranked
+        .into_iter()
+        .skip(req.page as usize * page_size)
+        .take(page_size)
+        .collect::<Vec<_>>();
+    if page.is_empty() {
+        return Ok(SearchResponse {
+            hits: vec![],
+            total,
+        });
+    }
+
+    let query = QueryArticle {
+        ids: page.iter().map(|(id, _)| *id).collect(),
+        visibility: req.visibility,
+        ..QueryArticle::default()
+    };
+    let mut articles = db
+        .query_articles(query)
+        .await?
+        .into_iter()
+        .map(|article| (article.id, article))
+        .collect::<HashMap<_, _>>();
+
+    let hits = page
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let mut article = articles.remove(&id)?;
+            let snippet = snippet(&article.content, terms)
+                .unwrap_or_else(|| highlight(&article.summary, terms));
+            let title = highlight(&article.title, terms);
+            article.content = String::new();
+            Some(SearchHit {
+                article: Some(article),
+                score,
+                title,
+                snippet,
+            })
+        })
+        .collect();
+    Ok(SearchResponse { hits, total })
+}
+
+/// `text` html escaped, with every occurrence of `terms` wrapped in `<mark>`.
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let marks = find_terms(&chars, terms);
+    render(&chars, &marks, 0, chars.len())
+}
+
+/// A highlighted window of `text` around its first matched term, `None` when nothing matched.
+pub fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let marks = find_terms(&chars, terms);
+    let first = marks.iter().position(|marked| *marked)?;
+
+    let start = first.saturating_sub(SNIPPET_BEFORE);
+    let end = (start + SNIPPET_LEN).min(chars.len());
+    let mut snippet = render(&chars, &marks, start, end);
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+/// Which chars of `chars` belong to an occurrence of one of `terms`, case-insensitively.
+fn find_terms(chars: &[char], terms: &[String]) -> Vec<bool> {
+    let lower = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let mut marks = vec![false; chars.len()];
+
+    for term in terms {
+        let term = term.chars().collect::<Vec<_>>();
+        if term.is_empty() || term.len() > lower.len() {
+            continue;
+        }
+        for start in 0..=lower.len() - term.len() {
+            if lower[start..start + term.len()] == term[..] {
+                marks[start..start + term.len()].fill(true);
+            }
+        }
+    }
+    marks
+}
+
+fn render(chars: &[char], marks: &[bool], start: usize, end: usize) -> String {
+    let mut html = String::new();
+    let mut in_mark = false;
+    for i in start..end {
+        if marks[i] != in_mark {
+            html.push_str(if marks[i] { "<mark>" } else { "</mark>" });
+            in_mark = marks[i];
+        }
+        match chars[i] {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            '\n' | '\r' | '\t' => html.push(' '),
+            c => html.push(c),
+        }
+    }
+    if in_mark {
+        html.push_str("</mark>");
+    }
+    html
+}
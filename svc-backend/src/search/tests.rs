@@ -0,0 +1,47 @@
+use crate::search::*;
+
+#[test]
+fn tokenize_should_segment_chinese() {
+    let terms = tokenize("Rust 异步编程入门, Tokio!");
+    assert!(terms.contains(&"rust".to_string()));
+    assert!(terms.contains(&"异步".to_string()));
+    assert!(terms.contains(&"编程".to_string()));
+    assert!(terms.contains(&"tokio".to_string()));
+    assert!(!terms
+        .iter()
+        .any(|term| term.trim().is_empty() || term == ","));
+}
+
+#[test]
+fn rank_should_prefer_full_coverage() {
+    let terms = vec!["异步".to_string(), "编程".to_string()];
+    let matches = vec![
+        (1, "异步".to_string(), 1.0),
+        (1, "编程".to_string(), 1.0),
+        (2, "异步".to_string(), 3.0),
+    ];
+    let ranked = rank(&terms, &matches, 10);
+    assert_eq!(ranked.len(), 2);
+    assert_eq!(ranked[0].0, 1);
+}
+
+#[test]
+fn highlight_should_escape_and_mark() {
+    let terms = vec!["rust".to_string(), "异步".to_string()];
+    assert_eq!(
+        highlight("<b>Rust</b> 的异步", &terms),
+        "&lt;b&gt;<mark>Rust</mark>&lt;/b&gt; 的<mark>异步</mark>"
+    );
+}
+
+#[test]
+fn snippet_should_cut_around_first_match() {
+    let text = format!("{}异步编程{}", "前".repeat(100), "后".repeat(200));
+    let terms = vec!["异步".to_string()];
+    let res = snippet(&text, &terms).unwrap();
+    assert!(res.starts_with('…'));
+    assert!(res.ends_with('…'));
+    assert!(res.contains("<mark>异步</mark>编程"));
+
+    assert!(snippet("nothing here", &terms).is_none());
+}
@@ -4,39 +4,62 @@ use util_pb::blog_service_server::BlogService;
 use util_pb::create_request::Create;
 use util_pb::delete_request::Delete;
 use util_pb::query_request::Query;
+use util_pb::restore_request::Restore;
+use util_pb::transfer::Cursor;
 use util_pb::update_request::Update;
 use util_pb::{
-    CreateRequest, CreateResponse, DeleteRequest, DeleteResponse, QueryRequest, QueryResponse,
-    UpdateRequest, UpdateResponse,
+    ArchiveRequest, ArchiveResponse, BatchRequest, BatchResponse, BatchResult, CreateRequest,
+    CreateResponse, DeleteRequest, DeleteResponse, ListRevisionsRequest, ListRevisionsResponse,
+    MergeTagsRequest, MergeTagsResponse, QueryRequest, QueryResponse, ResolveTagsRequest,
+    ResolveTagsResponse, RestoreRequest, RestoreResponse, RestoreRevisionRequest,
+    RestoreRevisionResponse, SearchRequest, SearchResponse, SortKey, UpdateRequest, UpdateResponse,
 };
 
 use crate::service::BackendInnerService;
 use crate::storage::BlogDB;
 
 #[tonic::async_trait]
-impl BlogService for BackendInnerService {
+impl<DB: BlogDB + 'static> BlogService for BackendInnerService<DB> {
     async fn query(
         &self,
         request: Request<QueryRequest>,
     ) -> Result<Response<QueryResponse>, Status> {
         let req = request.into_inner();
         match req.query.unwrap() {
-            Query::QueryArticle(qa) => {
-                let res = self.db_pool.query_articles(qa).await?;
+            Query::QueryArticle(mut qa) => {
+                let total = self.db.count_articles(qa.clone()).await?;
+
+                // fetch one more article to tell whether there is a next page
+                let page_size = qa.page_size as usize;
+                let sort_key = SortKey::from_i32(qa.sort_key).unwrap_or_default();
+                if page_size > 0 {
+                    qa.page_size += 1;
+                }
+                let mut res = self.db.query_articles(qa).await?;
+
+                let next_cursor = if page_size > 0 && res.len() > page_size {
+                    res.truncate(page_size);
+                    Cursor::from_article(res.last().unwrap(), sort_key).encode()
+                } else {
+                    String::new()
+                };
+
                 Ok(Response::new(QueryResponse {
                     articles: res,
+                    next_cursor,
+                    total,
                     ..QueryResponse::default()
                 }))
             }
             Query::QueryCategory(qc) => {
-                let res = self.db_pool.query_categories(qc).await?;
+                let res = self.db.query_categories(qc).await?;
                 Ok(Response::new(QueryResponse {
                     categories: res,
                     ..QueryResponse::default()
                 }))
             }
             Query::QueryTag(qt) => {
-                let res = self.db_pool.query_tags(qt).await?;
+                let res = self.db.query_tags(qt).await?;
                 Ok(Response::new(QueryResponse {
                     tags: res,
                     ..QueryResponse::default()
@@ -52,15 +75,15 @@ impl BlogService for BackendInnerService {
         let req = request.into_inner();
         match req.create.unwrap() {
             Create::Article(ca) => {
-                let res = self.db_pool.add_article(ca).await?;
+                let res = self.db.add_article(ca).await?;
                 Ok(Response::new(CreateResponse { id: res }))
             }
             Create::Category(cc) => {
-                let res = self.db_pool.add_category(cc).await?;
+                let res = self.db.add_category(cc).await?;
                 Ok(Response::new(CreateResponse { id: res }))
             }
             Create::Tag(ct) => {
-                let res = self.db_pool.add_tag(ct).await?;
+                let res = self.db.add_tag(ct).await?;
                 Ok(Response::new(CreateResponse { id: res }))
             }
         }
@@ -73,15 +96,15 @@ impl BlogService for BackendInnerService {
         let req = request.into_inner();
         match req.update.unwrap() {
             Update::Article(ua) => {
-                let res = self.db_pool.edit_article(ua).await?;
+                let res = self.db.edit_article(ua, req.update_mask).await?;
                 Ok(Response::new(UpdateResponse { id: res }))
             }
             Update::Category(uc) => {
-                let res = self.db_pool.edit_category(uc).await?;
+                let res = self.db.edit_category(uc, req.update_mask).await?;
                 Ok(Response::new(UpdateResponse { id: res }))
             }
             Update::Tag(ut) => {
-                let res = self.db_pool.edit_tag(ut).await?;
+                let res = self.db.edit_tag(ut, req.update_mask).await?;
                 Ok(Response::new(UpdateResponse { id: res }))
             }
         }
@@ -94,17 +117,138 @@ impl BlogService for BackendInnerService {
         let req = request.into_inner();
         match req.delete.unwrap() {
             Delete::ArticleId(id) => {
-                self.db_pool.delete_article(id).await?;
+                if req.purge {
+                    self.db.purge_article(id).await?;
+                } else {
+                    self.db.delete_article(id).await?;
+                }
                 Ok(Response::new(DeleteResponse { id }))
             }
             Delete::CategoryId(id) => {
-                self.db_pool.delete_category(id).await?;
+                if req.purge {
+                    self.db.purge_category(id).await?;
+                } else {
+                    self.db.delete_category(id, req.reassign_to).await?;
+                }
                 Ok(Response::new(DeleteResponse { id }))
             }
             Delete::TagId(id) => {
-                self.db_pool.delete_tag(id).await?;
+                if req.purge {
+                    self.db.purge_tag(id).await?;
+                } else {
+                    self.db.delete_tag(id).await?;
+                }
                 Ok(Response::new(DeleteResponse { id }))
             }
         }
     }
+
+    async fn restore(
+        &self,
+        request: Request<RestoreRequest>,
+    ) -> Result<Response<RestoreResponse>, Status> {
+        let req = request.into_inner();
+        match req.restore.unwrap() {
+            Restore::ArticleId(id) => {
+                self.db.restore_article(id).await?;
+                Ok(Response::new(RestoreResponse { id }))
+            }
+            Restore::CategoryId(id) => {
+                self.db.restore_category(id).await?;
+                Ok(Response::new(RestoreResponse { id }))
+            }
+            Restore::TagId(id) => {
+                self.db.restore_tag(id).await?;
+                Ok(Response::new(RestoreResponse { id }))
+            }
+        }
+    }
+
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        let req = request.into_inner();
+        let res = self.db.search(req).await?;
+        Ok(Response::new(res))
+    }
+
+    async fn list_revisions(
+        &self,
+        request: Request<ListRevisionsRequest>,
+    ) -> Result<Response<ListRevisionsResponse>, Status> {
+        let req = request.into_inner();
+        let revisions = self.db.list_revisions(req.article_id).await?;
+        Ok(Response::new(ListRevisionsResponse { revisions }))
+    }
+
+    async fn restore_revision(
+        &self,
+        request: Request<RestoreRevisionRequest>,
+    ) -> Result<Response<RestoreRevisionResponse>, Status> {
+        let req = request.into_inner();
+        let article_id = self.db.restore_revision(req.revision_id).await?;
+        Ok(Response::new(RestoreRevisionResponse { article_id }))
+    }
+
+    async fn merge_tags(
+        &self,
+        request: Request<MergeTagsRequest>,
+    ) -> Result<Response<MergeTagsResponse>, Status> {
+        let req = request.into_inner();
+        let articles = self.db.merge_tags(req.source_ids, req.target_id).await?;
+        Ok(Response::new(MergeTagsResponse {
+            id: req.target_id,
+            articles,
+        }))
+    }
+
+    async fn resolve_tags(
+        &self,
+        request: Request<ResolveTagsRequest>,
+    ) -> Result<Response<ResolveTagsResponse>, Status> {
+        let req = request.into_inner();
+        let (ids, unknown) = match req.lookup_only {
+            true => self.db.find_tags(req.names).await?,
+            false => (self.db.resolve_tags(req.names).await?, vec![]),
+        };
+        Ok(Response::new(ResolveTagsResponse { ids, unknown }))
+    }
+
+    async fn archive(
+        &self,
+        request: Request<ArchiveRequest>,
+    ) -> Result<Response<ArchiveResponse>, Status> {
+        let req = request.into_inner();
+        let buckets = self.db.archive(req).await?;
+        Ok(Response::new(ArchiveResponse { buckets }))
+    }
+
+    async fn batch(
+        &self,
+        request: Request<BatchRequest>,
+    ) -> Result<Response<BatchResponse>, Status> {
+        let req = request.into_inner();
+        let results = self
+            .db
+            .batch(req.operations)
+            .await?
+            .into_iter()
+            .map(|res| match res {
+                Ok(id) => BatchResult {
+                    id,
+                    ..BatchResult::default()
+                },
+                Err(e) => {
+                    let status = Status::from(e);
+                    BatchResult {
+                        id: 0,
+                        code: status.code() as i32,
+                        message: status.message().to_string(),
+                    }
+                }
+            })
+            .collect();
+        Ok(Response::new(BatchResponse { results }))
+    }
 }
@@ -1,28 +1,27 @@
 use std::sync::Arc;
 
-use crate::storage::DBPool;
+use crate::storage::{BlogDB, DBPool};
 
 pub mod implements;
 
 #[cfg(test)]
 mod tests;
 
-pub struct BackendInnerService {
-    db_pool: Arc<DBPool>,
+/// The Blog service over a `BlogDB`, Postgres unless told otherwise.
+pub struct BackendInnerService<DB = DBPool> {
+    db: Arc<DB>,
 }
 
-impl BackendInnerService {
-    pub fn new(db_pool: DBPool) -> Self {
-        Self {
-            db_pool: Arc::new(db_pool),
-        }
+impl<DB: BlogDB> BackendInnerService<DB> {
+    pub fn new(db: DB) -> Self {
+        Self { db: Arc::new(db) }
     }
 }
 
-impl Clone for BackendInnerService {
+impl<DB> Clone for BackendInnerService<DB> {
     fn clone(&self) -> Self {
         Self {
-            db_pool: Arc::clone(&self.db_pool),
+            db: Arc::clone(&self.db),
         }
     }
 }
@@ -1,19 +1,22 @@
 use std::path::Path;
 
 use sqlx_db_tester::TestPg;
-use tonic::Request;
+use tonic::{Code, Request};
 
+use util_pb::batch_operation::Operation;
 use util_pb::blog_service_server::BlogService;
 use util_pb::create_request::Create;
 use util_pb::delete_request::Delete;
 use util_pb::query_request::Query;
+use util_pb::restore_request::Restore;
 use util_pb::update_request::Update;
 use util_pb::{
-    Article, CreateRequest, DeleteRequest, QueryArticle, QueryCategory, QueryRequest, UpdateRequest,
+    Article, BatchOperation, BatchRequest, CreateRequest, DeleteRequest, QueryArticle,
+    QueryCategory, QueryRequest, RestoreRequest, SortKey, UpdateRequest,
 };
 
 use crate::service::BackendInnerService;
-use crate::storage::DBPool;
+use crate::storage::{BlogDB, DBPool, MemoryDB};
 
 async fn load_test_db() -> TestPg {
     dotenv::dotenv().ok();
@@ -24,14 +27,22 @@ async fn load_test_db() -> TestPg {
     )
 }
 
+async fn postgres_service(tdb: &TestPg) -> BackendInnerService {
+    BackendInnerService::new(DBPool::new(tdb.get_pool().await))
+}
+
 #[tokio::test]
 async fn article_service_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    article_service(postgres_service(&tdb).await).await;
+}
 
-    let inner_service = BackendInnerService::new(db);
+#[tokio::test]
+async fn article_service_should_work_in_memory() {
+    article_service(BackendInnerService::new(MemoryDB::sample())).await;
+}
 
+async fn article_service<DB: BlogDB + 'static>(inner_service: BackendInnerService<DB>) {
     // add
     let article = Article {
         title: "test_add".to_string(),
@@ -64,28 +75,116 @@ async fn article_service_should_work() {
     };
     let edit = UpdateRequest {
         update: Some(Update::Article(article)),
+        update_mask: None,
     };
     let req = Request::new(edit);
     let res = inner_service.update(req).await;
     assert!(res.is_ok());
 
+    // a stale edit
+    let article = Article {
+        id,
+        title: "test_stale".to_string(),
+        version: 1,
+        ..Article::default()
+    };
+    let edit = UpdateRequest {
+        update: Some(Update::Article(article)),
+        update_mask: None,
+    };
+    let res = inner_service.update(Request::new(edit)).await;
+    assert_eq!(res.unwrap_err().code(), Code::Aborted);
+
     // delete
     let delete = DeleteRequest {
         delete: Some(Delete::ArticleId(id)),
+        purge: false,
+        reassign_to: None,
     };
     let req = Request::new(delete);
     let res = inner_service.delete(req).await;
     assert!(res.is_ok());
+
+    // restore
+    let restore = RestoreRequest {
+        restore: Some(Restore::ArticleId(id)),
+    };
+    let req = Request::new(restore);
+    let res = inner_service.restore(req).await;
+    assert!(res.is_ok());
+
+    // purge
+    let purge = DeleteRequest {
+        delete: Some(Delete::ArticleId(id)),
+        purge: true,
+        reassign_to: None,
+    };
+    let req = Request::new(purge.clone());
+    let res = inner_service.delete(req).await;
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+
+    let delete = DeleteRequest {
+        purge: false,
+        reassign_to: None,
+        ..purge.clone()
+    };
+    inner_service.delete(Request::new(delete)).await.unwrap();
+    let req = Request::new(purge);
+    let res = inner_service.delete(req).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn article_pagination_service_should_work() {
+    let tdb = load_test_db().await;
+    article_pagination_service(postgres_service(&tdb).await).await;
+}
+
+#[tokio::test]
+async fn article_pagination_service_should_work_in_memory() {
+    article_pagination_service(BackendInnerService::new(MemoryDB::sample())).await;
+}
+
+async fn article_pagination_service<DB: BlogDB + 'static>(inner_service: BackendInnerService<DB>) {
+    let mut ids = vec![];
+    let mut cursor = String::new();
+    loop {
+        let query = QueryRequest {
+            query: Some(Query::QueryArticle(QueryArticle {
+                page_size: 1,
+                sort_key: SortKey::Id as i32,
+                cursor,
+                ..QueryArticle::default()
+            })),
+        };
+        let res = inner_service
+            .query(Request::new(query))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(res.total, 2);
+        ids.extend(res.articles.iter().map(|article| article.id));
+
+        if res.next_cursor.is_empty() {
+            break;
+        }
+        cursor = res.next_cursor;
+    }
+    assert_eq!(ids, vec![1001, 1000]);
 }
 
 #[tokio::test]
 async fn category_service_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    category_service(postgres_service(&tdb).await).await;
+}
 
-    let inner_service = BackendInnerService::new(db);
+#[tokio::test]
+async fn category_service_should_work_in_memory() {
+    category_service(BackendInnerService::new(MemoryDB::sample())).await;
+}
 
+async fn category_service<DB: BlogDB + 'static>(inner_service: BackendInnerService<DB>) {
     // add
     let category = util_pb::Category {
         name: "test_add".to_string(),
@@ -111,9 +210,11 @@ async fn category_service_should_work() {
     let category = util_pb::Category {
         id,
         name: "test_edit".to_string(),
+        ..util_pb::Category::default()
     };
     let edit = UpdateRequest {
         update: Some(Update::Category(category)),
+        update_mask: None,
     };
     let req = Request::new(edit);
     let res = inner_service.update(req).await;
@@ -122,6 +223,8 @@ async fn category_service_should_work() {
     // delete
     let delete = DeleteRequest {
         delete: Some(Delete::CategoryId(id)),
+        purge: false,
+        reassign_to: None,
     };
     let req = Request::new(delete);
     let res = inner_service.delete(req).await;
@@ -131,11 +234,15 @@ async fn category_service_should_work() {
 #[tokio::test]
 async fn tag_service_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    tag_service(postgres_service(&tdb).await).await;
+}
 
-    let inner_service = BackendInnerService::new(db);
+#[tokio::test]
+async fn tag_service_should_work_in_memory() {
+    tag_service(BackendInnerService::new(MemoryDB::sample())).await;
+}
 
+async fn tag_service<DB: BlogDB + 'static>(inner_service: BackendInnerService<DB>) {
     // add
     let tag = util_pb::Tag {
         name: "test_add".to_string(),
@@ -161,9 +268,11 @@ async fn tag_service_should_work() {
     let tag = util_pb::Tag {
         id,
         name: "test_edit".to_string(),
+        ..util_pb::Tag::default()
     };
     let edit = UpdateRequest {
         update: Some(Update::Tag(tag)),
+        update_mask: None,
     };
     let req = Request::new(edit);
     let res = inner_service.update(req).await;
@@ -172,8 +281,99 @@ async fn tag_service_should_work() {
     // delete
     let delete = DeleteRequest {
         delete: Some(Delete::TagId(id)),
+        purge: false,
+        reassign_to: None,
     };
     let req = Request::new(delete);
     let res = inner_service.delete(req).await;
     assert!(res.is_ok());
 }
+
+#[tokio::test]
+async fn batch_service_should_work() {
+    let tdb = load_test_db().await;
+    batch_service(postgres_service(&tdb).await).await;
+}
+
+#[tokio::test]
+async fn batch_service_should_work_in_memory() {
+    batch_service(BackendInnerService::new(MemoryDB::sample())).await;
+}
+
+async fn batch_service<DB: BlogDB + 'static>(inner_service: BackendInnerService<DB>) {
+    let create = |create| BatchOperation {
+        operation: Some(Operation::Create(CreateRequest {
+            create: Some(create),
+        })),
+    };
+    let delete = |delete| BatchOperation {
+        operation: Some(Operation::Delete(DeleteRequest {
+            delete: Some(delete),
+            purge: false,
+            reassign_to: None,
+        })),
+    };
+    let stale = Article {
+        id: 1001,
+        title: "stale".to_string(),
+        version: 99,
+        ..Article::default()
+    };
+    let operations = vec![
+        create(Create::Tag(util_pb::Tag {
+            name: "batch".to_string(),
+            ..util_pb::Tag::default()
+        })),
+        // the same tag twice is linked once
+        create(Create::Article(Article {
+            title: "batch".to_string(),
+            content: "batch content".to_string(),
+            category_id: 1,
+            tags_id: vec![1, 1, 2],
+            ..Article::default()
+        })),
+        BatchOperation {
+            operation: Some(Operation::Update(UpdateRequest {
+                update: Some(Update::Article(stale)),
+                update_mask: None,
+            })),
+        },
+        delete(Delete::TagId(0)),
+        delete(Delete::ArticleId(1000)),
+        BatchOperation::default(),
+    ];
+    let req = Request::new(BatchRequest { operations });
+    let results = inner_service.batch(req).await.unwrap().into_inner().results;
+    let codes = results.iter().map(|r| r.code).collect::<Vec<_>>();
+    assert_eq!(
+        codes,
+        vec![
+            Code::Ok as i32,
+            Code::Ok as i32,
+            Code::Aborted as i32,
+            Code::InvalidArgument as i32,
+            Code::Ok as i32,
+            Code::InvalidArgument as i32,
+        ]
+    );
+    assert_eq!(results[4].id, 1000);
+    assert!(!results[2].message.is_empty());
+
+    // the operations around the failed ones are kept
+    let query = |ids| {
+        Request::new(QueryRequest {
+            query: Some(Query::QueryArticle(QueryArticle {
+                ids,
+                ..QueryArticle::default()
+            })),
+        })
+    };
+    let res = inner_service.query(query(vec![results[1].id])).await;
+    let mut tags_id = res.unwrap().into_inner().articles.remove(0).tags_id;
+    tags_id.sort();
+    assert_eq!(tags_id, vec![1, 2]);
+    let res = inner_service.query(query(vec![1000, 1001])).await;
+    let articles = res.unwrap().into_inner().articles;
+    assert_eq!(articles.len(), 1);
+    assert_eq!(articles[0].title, "test_title1");
+}
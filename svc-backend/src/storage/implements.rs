@@ -1,226 +1,360 @@
 use std::collections::hash_map::RandomState;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
-use sqlx::Row;
+use chrono::{DateTime, Local};
+use sqlx::{Postgres, QueryBuilder, Row};
 use tracing::trace;
 
-use util_pb::transfer::AS;
+use util_pb::transfer::{
+    article_columns, check_calendar, timezone_or_utc, Pagination, PUBLIC_ARTICLES,
+};
 use util_pb::{
-    get_summary, to_timestamp, transfer::ToSql, Article, ArticleState, Category, QueryArticle,
-    QueryCategory, QueryTag, Tag,
+    to_timestamp, transfer::ToSql, ArchiveBucket, ArchiveRequest, Article, ArticleRevision,
+    BatchOperation, Category, FieldMask, QueryArticle, QueryCategory, QueryTag, SearchRequest,
+    SearchResponse, Tag, Visibility,
 };
 
 use crate::error::Result;
+use crate::search;
 use crate::storage::traits::{BlogDB, ID};
+use crate::storage::writes;
 use crate::storage::DBPool;
 use crate::BackendError;
 
 #[tonic::async_trait]
 impl BlogDB for DBPool {
-    async fn query_articles(&self, mut req: QueryArticle) -> Result<Vec<Article>> {
-        let mut article_ids = if req.tags_id.is_empty() {
-            vec![]
-        } else {
-            let sql = "SELECT article_id FROM blog.article_tag WHERE tag_id = ANY($1) group by article_id having count(article_id) >= $2;"; // 逻辑：并
-            let rows = sqlx::query(sql)
-                .bind(&req.tags_id)
-                .bind(req.tags_id.len() as i32)
-                .fetch_all(self.deref())
-                .await?;
-            rows.into_iter().map(|row| row.get(0)).collect::<Vec<i32>>()
-        };
-
-        req.ids.append(&mut article_ids);
-        let condition = req.to_sql();
-        let sql = format!("SELECT * FROM blog.articles  WHERE {}", condition);
-        let sql = format!("SELECT * FROM ({}) AS first LEFT OUTER JOIN blog.article2tag AS second ON first.id = second.article_id;", sql);
+    async fn query_articles(&self, req: QueryArticle) -> Result<Vec<Article>> {
+        check_calendar(&req).map_err(BackendError::InvalidRequest)?;
+        writes::parse_timezone(&req.timezone)?;
+        let pagination = Pagination::from_query(&req).map_err(BackendError::InvalidRequest)?;
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT * FROM (SELECT {} FROM blog.articles WHERE ",
+            article_columns(req.projection)
+        ));
+        req.to_sql(&mut builder);
+        pagination.push_cursor(&mut builder);
+        pagination.push_order(&mut builder);
+        pagination.push_limit(&mut builder);
+        builder.push(
+            ") AS first LEFT OUTER JOIN blog.article2tag AS second ON first.id = second.article_id",
+        );
+        pagination.push_order(&mut builder);
 
-        let res = sqlx::query_as::<_, Article>(&sql)
+        let res = builder
+            .build_query_as::<Article>()
             .fetch_all(self.deref())
             .await?;
         tracing::error!("res: {:?}", res);
         Ok(res)
     }
 
-    async fn add_article(&self, article: Article) -> Result<ID> {
-        let summary = if article.summary.is_empty() {
-            get_summary(&article.content)
-        } else {
-            article.summary
-        };
+    async fn count_articles(&self, req: QueryArticle) -> Result<i64> {
+        check_calendar(&req).map_err(BackendError::InvalidRequest)?;
+        writes::parse_timezone(&req.timezone)?;
+        let mut builder =
+            QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM blog.articles WHERE ");
+        req.to_sql(&mut builder);
 
-        // step1: bolg.articles
-        let sql = "INSERT INTO blog.articles (title, content, summary, state, category_id) VALUES ($1, $2, $3, $4, $5) RETURNING id;";
-        let state = AS::try_from(article.state).unwrap();
+        let total = builder.build().fetch_one(self.deref()).await?.get(0);
+        Ok(total)
+    }
 
-        let id = sqlx::query(sql)
-            .bind(&article.title)
-            .bind(&article.content)
-            .bind(summary)
-            .bind(state)
-            .bind(article.category_id)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+    async fn add_article(&self, article: Article) -> Result<ID> {
+        let mut tx = self.begin().await?;
+        let id = writes::add_article(&mut tx, article).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
 
-        // step2: blog.article_tag
-        let sql = "INSERT INTO blog.article_tag (article_id, tag_id) VALUES ($1, $2);";
-        let tags_id = article.tags_id;
-        // tags_id.push(0);
-        for tag_id in tags_id {
-            sqlx::query(sql)
-                .bind(id)
-                .bind(tag_id)
-                .execute(self.deref())
-                .await?;
-        }
+    async fn edit_article(&self, article: Article, mask: Option<FieldMask>) -> Result<ID> {
+        let mut tx = self.begin().await?;
+        let id = writes::edit_article(&mut tx, article, mask).await?;
+        tx.commit().await?;
         Ok(id)
     }
 
-    async fn edit_article(&self, article: Article) -> Result<ID> {
-        let tags_id = article.tags_id.clone();
-        let article_id = article.id;
-        // step1
-        let kv_pairs_without_tags = article.to_sql();
-        if kv_pairs_without_tags.is_empty() {
-            return Err(BackendError::InvalidRequest(
-                "Empty update article request.".to_string(),
-            ));
-        }
+    async fn delete_article(&self, id: ID) -> Result<()> {
+        writes::move_to_trash(self.deref(), "blog.articles", id, true).await
+    }
 
-        let sql = format!(
-            "UPDATE blog.articles SET {} WHERE id = $1 RETURNING id;",
-            kv_pairs_without_tags
-        );
-        let id = sqlx::query(&sql)
-            .bind(article_id)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+    async fn restore_article(&self, id: ID) -> Result<()> {
+        writes::move_to_trash(self.deref(), "blog.articles", id, false).await
+    }
 
-        // step2
-        let old_tags = self.article_to_tags(article_id).await?;
-        let old_tags = HashSet::<_>::from_iter(old_tags);
-        let new_tags = HashSet::<_>::from_iter(tags_id);
-
-        let need_add_tags = new_tags
-            .difference(&old_tags)
-            .cloned()
-            .collect::<Vec<i32>>();
-        let need_delete_tags = old_tags
-            .difference(&new_tags)
-            .cloned()
-            .collect::<Vec<i32>>();
-
-        let sql = "INSERT INTO blog.article_tag (article_id, tag_id) VALUES ($1, $2);";
-        for tag_id in need_add_tags {
-            sqlx::query(sql)
-                .bind(id)
-                .bind(tag_id)
-                .execute(self.deref())
-                .await?;
-        }
+    async fn purge_article(&self, id: ID) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::purge_article(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        let sql = "DELETE FROM blog.article_tag WHERE article_id = $1 AND tag_id = $2;";
-        for tag_id in need_delete_tags {
-            sqlx::query(sql)
-                .bind(id)
-                .bind(tag_id)
-                .execute(self.deref())
-                .await?;
-        }
-        Ok(id)
+    async fn list_revisions(&self, article_id: ID) -> Result<Vec<ArticleRevision>> {
+        let sql = "SELECT * FROM blog.article_revisions WHERE article_id = $1 ORDER BY id DESC;";
+        let res = sqlx::query_as::<_, ArticleRevision>(sql)
+            .bind(article_id)
+            .fetch_all(self.deref())
+            .await?;
+        Ok(res)
     }
 
-    async fn delete_article(&self, id: ID) -> Result<()> {
-        // step2
-        let sql = "DELETE FROM blog.article_tag WHERE article_id = $1;";
-        sqlx::query(sql).bind(id).execute(self.deref()).await?;
+    async fn restore_revision(&self, revision_id: ID) -> Result<ID> {
+        let sql = "SELECT * FROM blog.article_revisions WHERE id = $1;";
+        let revision = sqlx::query_as::<_, ArticleRevision>(sql)
+            .bind(revision_id)
+            .fetch_optional(self.deref())
+            .await?
+            .ok_or_else(|| {
+                BackendError::InvalidRequest(format!("No such revision: {}", revision_id))
+            })?;
 
-        // step1
-        let sql = "DELETE FROM blog.articles WHERE id = $1;";
-        sqlx::query(sql).bind(id).execute(self.deref()).await?;
-        Ok(())
+        let article = Article {
+            id: revision.article_id,
+            title: revision.title,
+            content: revision.content,
+            summary: revision.summary,
+            state: revision.state,
+            category_id: revision.category_id,
+            tags_id: revision.tags_id,
+            ..Article::default()
+        };
+        let mask = FieldMask {
+            paths: ["title", "content", "summary", "category_id", "tags_id"]
+                .map(String::from)
+                .to_vec(),
+        };
+        self.edit_article(article, Some(mask)).await
     }
 
     async fn query_categories(&self, req: QueryCategory) -> Result<Vec<Category>> {
-        let condition = req.to_sql();
-        let sql = format!("SELECT * FROM blog.categories WHERE {}", condition);
-        let res = sqlx::query_as::<_, Category>(&sql)
+        let sql = format!(
+            "WITH RECURSIVE subtree (root, id) AS (\
+                SELECT id, id FROM blog.categories \
+                UNION SELECT subtree.root, c.id FROM blog.categories AS c \
+                JOIN subtree ON c.parent_id = subtree.id), \
+            counts AS (SELECT root, COUNT(*) FILTER (WHERE {}) AS published_count, \
+                COUNT(*) AS total_count FROM subtree \
+                JOIN blog.articles AS a ON a.category_id = subtree.id AND a.deleted_at IS NULL \
+                GROUP BY root) \
+            SELECT categories.*, COALESCE(published_count, 0) AS published_count, \
+            COALESCE(total_count, 0) AS total_count \
+            FROM blog.categories LEFT JOIN counts ON root = id WHERE ",
+            PUBLIC_ARTICLES
+        );
+        let mut builder = QueryBuilder::<Postgres>::new(sql);
+        req.to_sql(&mut builder);
+        let res = builder
+            .push(" ORDER BY id")
+            .build_query_as::<Category>()
             .fetch_all(self.deref())
             .await?;
         Ok(res)
     }
 
     async fn add_category(&self, category: Category) -> Result<ID> {
-        let sql = "INSERT INTO blog.categories (name) VALUES ($1) RETURNING id;";
-        let id = sqlx::query(sql)
-            .bind(&category.name)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+        let mut tx = self.begin().await?;
+        let id = writes::add_category(&mut tx, category).await?;
+        tx.commit().await?;
         Ok(id)
     }
 
-    async fn edit_category(&self, category: Category) -> Result<ID> {
-        let sql = "UPDATE blog.categories SET name = $1 WHERE id = $2 RETURNING id;";
-        let id = sqlx::query(sql)
-            .bind(&category.name)
-            .bind(category.id)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+    async fn edit_category(&self, category: Category, mask: Option<FieldMask>) -> Result<ID> {
+        let mut tx = self.begin().await?;
+        let id = writes::edit_category(&mut tx, category, mask).await?;
+        tx.commit().await?;
         Ok(id)
     }
 
-    async fn delete_category(&self, id: ID) -> Result<()> {
-        let sql = "Delete FROM blog.categories WHERE id = $1 RETURNING id;";
-        sqlx::query(sql).bind(id).execute(self.deref()).await?;
+    async fn delete_category(&self, id: ID, reassign_to: Option<ID>) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::delete_category(&mut tx, id, reassign_to).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore_category(&self, id: ID) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::restore_category(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn purge_category(&self, id: ID) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::purge_category(&mut tx, id).await?;
+        tx.commit().await?;
         Ok(())
     }
 
     async fn query_tags(&self, req: QueryTag) -> Result<Vec<Tag>> {
-        let condition = req.to_sql();
-        let sql = format!("SELECT * FROM blog.tags WHERE {}", condition);
-        let res = sqlx::query_as::<_, Tag>(&sql)
+        let sql = format!(
+            "WITH counts AS (SELECT tag_id, COUNT(*) FILTER (WHERE {}) AS published_count, \
+                COUNT(*) AS total_count FROM blog.article_tag \
+                JOIN blog.articles ON id = article_id AND deleted_at IS NULL \
+                GROUP BY tag_id) \
+            SELECT tags.*, COALESCE(published_count, 0) AS published_count, \
+            COALESCE(total_count, 0) AS total_count \
+            FROM blog.tags LEFT JOIN counts ON tag_id = id WHERE ",
+            PUBLIC_ARTICLES
+        );
+        let mut builder = QueryBuilder::<Postgres>::new(sql);
+        req.to_sql(&mut builder);
+        let res = builder
+            .push(" ORDER BY id")
+            .build_query_as::<Tag>()
             .fetch_all(self.deref())
             .await?;
         Ok(res)
     }
 
     async fn add_tag(&self, tag: Tag) -> Result<ID> {
-        let sql = "INSERT INTO blog.tags (name) VALUES ($1) RETURNING id;";
-        let id = sqlx::query(sql)
-            .bind(&tag.name)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+        let mut tx = self.begin().await?;
+        let id = writes::add_tag(&mut tx, tag).await?;
+        tx.commit().await?;
         Ok(id)
     }
 
-    async fn edit_tag(&self, tag: Tag) -> Result<ID> {
-        let sql = "UPDATE blog.tags SET name = $1 WHERE id = $2 RETURNING id;";
-        let id = sqlx::query(sql)
-            .bind(&tag.name)
-            .bind(tag.id)
-            .fetch_one(self.deref())
-            .await?
-            .get(0);
+    async fn edit_tag(&self, tag: Tag, mask: Option<FieldMask>) -> Result<ID> {
+        let mut tx = self.begin().await?;
+        let id = writes::edit_tag(&mut tx, tag, mask).await?;
+        tx.commit().await?;
         Ok(id)
     }
 
     async fn delete_tag(&self, id: ID) -> Result<()> {
-        // step2
-        let sql = "DELETE FROM blog.article_tag WHERE tag_id = $1;";
-        sqlx::query(sql).bind(id).execute(self.deref()).await?;
+        let mut tx = self.begin().await?;
+        writes::delete_tag(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore_tag(&self, id: ID) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::restore_tag(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        // step1
-        let sql = "Delete FROM blog.tags WHERE id = $1 RETURNING id;";
-        sqlx::query(sql).bind(id).execute(self.deref()).await?;
+    async fn purge_tag(&self, id: ID) -> Result<()> {
+        let mut tx = self.begin().await?;
+        writes::purge_tag(&mut tx, id).await?;
+        tx.commit().await?;
         Ok(())
     }
 
+    async fn merge_tags(&self, source_ids: Vec<ID>, target_id: ID) -> Result<i32> {
+        if source_ids.is_empty() || source_ids.contains(&target_id) || source_ids.contains(&0) {
+            return Err(BackendError::InvalidRequest(format!(
+                "Cannot merge tags {:?} into {}.",
+                source_ids, target_id
+            )));
+        }
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT id FROM blog.tags WHERE id = $1 AND deleted_at IS NULL FOR UPDATE;";
+        sqlx::query(sql)
+            .bind(target_id)
+            .fetch_optional(&mut tx)
+            .await?
+            .ok_or_else(|| BackendError::InvalidRequest(format!("No such tag: {}", target_id)))?;
+        let sql = "SELECT id FROM blog.tags WHERE id = ANY($1) FOR UPDATE;";
+        let found = sqlx::query(sql)
+            .bind(&source_ids)
+            .fetch_all(&mut tx)
+            .await?;
+        if found.len() != HashSet::<_, RandomState>::from_iter(&source_ids).len() {
+            return Err(BackendError::InvalidRequest(format!(
+                "No such tags: {:?}",
+                source_ids
+            )));
+        }
+
+        // step0: the articles, once each
+        let sql = "SELECT COUNT(DISTINCT article_id) FROM blog.article_tag WHERE tag_id = ANY($1);";
+        let articles: i64 = sqlx::query(sql)
+            .bind(&source_ids)
+            .fetch_one(&mut tx)
+            .await?
+            .get(0);
+        let sql = "INSERT INTO blog.article_tag (article_id, tag_id) \
+            SELECT DISTINCT article_id, $1 FROM blog.article_tag WHERE tag_id = ANY($2) \
+            ON CONFLICT DO NOTHING;";
+        sqlx::query(sql)
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut tx)
+            .await?;
+        let sql = "DELETE FROM blog.article_tag WHERE tag_id = ANY($1);";
+        sqlx::query(sql).bind(&source_ids).execute(&mut tx).await?;
+
+        // step1: the revisions, so restoring one does not bring a source back
+        let sql = "UPDATE blog.article_revisions SET tags_id = ARRAY(\
+            SELECT DISTINCT CASE WHEN tag_id = ANY($2) THEN $1 ELSE tag_id END FROM UNNEST(tags_id) AS tag_id) \
+            WHERE tags_id && $2;";
+        sqlx::query(sql)
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut tx)
+            .await?;
+
+        // step2: the names, those the sources were aliases for included
+        let sql = "UPDATE blog.tag_aliases SET tag_id = $1 WHERE tag_id = ANY($2);";
+        sqlx::query(sql)
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut tx)
+            .await?;
+        let sql = "INSERT INTO blog.tag_aliases (name, tag_id) \
+            SELECT name, $1 FROM blog.tags WHERE id = ANY($2) \
+            ON CONFLICT (name) DO UPDATE SET tag_id = EXCLUDED.tag_id;";
+        sqlx::query(sql)
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut tx)
+            .await?;
+
+        let sql = "DELETE FROM blog.tags WHERE id = ANY($1);";
+        sqlx::query(sql).bind(&source_ids).execute(&mut tx).await?;
+
+        tx.commit().await?;
+        Ok(articles as i32)
+    }
+
+    async fn resolve_tags(&self, names: Vec<String>) -> Result<Vec<ID>> {
+        let mut tx = self.begin().await?;
+        let mut ids = Vec::with_capacity(names.len());
+        for name in names.iter().map(|name| name.trim()) {
+            if name.is_empty() {
+                continue;
+            }
+            let id = writes::resolve_tag(&mut tx, name).await?;
+            // `Rust, rust` or a name and its alias
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    async fn find_tags(&self, names: Vec<String>) -> Result<(Vec<ID>, Vec<String>)> {
+        let mut tx = self.begin().await?;
+        let mut ids = Vec::with_capacity(names.len());
+        let mut unknown = vec![];
+        for name in names.iter().map(|name| name.trim()) {
+            if name.is_empty() {
+                continue;
+            }
+            match writes::find_tag(&mut tx, name).await? {
+                Some(id) if !ids.contains(&id) => ids.push(id),
+                Some(_) => {}
+                None => unknown.push(name.to_string()),
+            }
+        }
+        tx.commit().await?;
+        Ok((ids, unknown))
+    }
+
     async fn tag_to_articles(&self, tag_id: ID) -> Result<Vec<ID>> {
         let sql = "SELECT article_ids FROM blog.tag2article WHERE tag_id = $1";
         let res = sqlx::query(sql)
@@ -232,15 +366,131 @@ impl BlogDB for DBPool {
 
     async fn article_to_tags(&self, article_id: ID) -> Result<Vec<ID>> {
         let sql = "SELECT tag_ids FROM blog.article2tag WHERE article_id = $1";
+        // no row while all its tags are in the trash bin
         let res = sqlx::query(sql)
             .bind(article_id)
-            .fetch_one(self.deref())
+            .fetch_optional(self.deref())
             .await?;
-        let article_ids: Vec<i32> = res.get(0);
-        return if article_ids.is_empty() {
-            Ok(vec![0])
-        } else {
-            Ok(article_ids)
+        Ok(res.map(|row| row.get(0)).unwrap_or_default())
+    }
+
+    async fn archive(&self, req: ArchiveRequest) -> Result<Vec<ArchiveBucket>> {
+        writes::parse_timezone(&req.timezone)?;
+        let visible = match Visibility::from_i32(req.visibility) {
+            Some(Visibility::Public) => format!("deleted_at IS NULL AND {}", PUBLIC_ARTICLES),
+            _ => "deleted_at IS NULL".to_string(),
         };
+        let sql = format!(
+            "SELECT EXTRACT(YEAR FROM local)::INT, EXTRACT(MONTH FROM local)::INT, COUNT(*)::INT \
+            FROM (SELECT created_at AT TIME ZONE $1 AS local FROM blog.articles WHERE {}) AS a \
+            GROUP BY 1, 2 ORDER BY 1 DESC, 2 DESC;",
+            visible
+        );
+        let rows = sqlx::query(&sql)
+            .bind(timezone_or_utc(&req.timezone))
+            .fetch_all(self.deref())
+            .await?;
+        let buckets = rows
+            .into_iter()
+            .map(|row| ArchiveBucket {
+                year: row.get(0),
+                month: row.get(1),
+                count: row.get(2),
+            })
+            .collect();
+        Ok(buckets)
+    }
+
+    async fn batch(&self, operations: Vec<BatchOperation>) -> Result<Vec<Result<ID>>> {
+        let mut tx = self.begin().await?;
+        let results = writes::batch(&mut tx, operations).await?;
+        tx.commit().await?;
+        Ok(results)
+    }
+
+    async fn publish_due(&self, now: DateTime<Local>) -> Result<Vec<ID>> {
+        let sql = "UPDATE blog.articles SET state = 'published', version = version + 1 WHERE state = 'scheduled' AND publish_at <= $1 RETURNING id;";
+        let rows = sqlx::query(sql).bind(now).fetch_all(self.deref()).await?;
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn expire_due(&self, now: DateTime<Local>) -> Result<Vec<ID>> {
+        let sql = "UPDATE blog.articles SET state = 'hidden', expire_at = NULL, version = version + 1 WHERE state = 'published' AND expire_at <= $1 RETURNING id;";
+        let rows = sqlx::query(sql).bind(now).fetch_all(self.deref()).await?;
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
+        if req.page < 0 || req.page_size < 0 {
+            return Err(BackendError::InvalidRequest(
+                "Negative search page.".to_string(),
+            ));
+        }
+        let terms = search::query_terms(&req.query);
+        if terms.is_empty() {
+            return Ok(SearchResponse::default());
+        }
+
+        // step0: the articles this request may see
+        let visible = match Visibility::from_i32(req.visibility) {
+            Some(Visibility::Public) => format!("deleted_at IS NULL AND {}", PUBLIC_ARTICLES),
+            _ => "deleted_at IS NULL".to_string(),
+        };
+
+        // step1: title, summary and content
+        let sql = format!("SELECT COUNT(*) FROM blog.articles WHERE {};", visible);
+        let total_articles: i64 = sqlx::query(&sql).fetch_one(self.deref()).await?.get(0);
+
+        let sql = format!("SELECT article_id, term, weight FROM blog.article_terms JOIN blog.articles ON id = article_id WHERE term = ANY($1) AND {};", visible);
+        let mut matches = sqlx::query(&sql)
+            .bind(&terms)
+            .fetch_all(self.deref())
+            .await?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect::<Vec<(ID, String, f32)>>();
+
+        // step2: tag names
+        let mut tag_terms = HashMap::new();
+        for tag in self.query_tags(QueryTag::default()).await? {
+            let matched = search::tokenize(&tag.name)
+                .into_iter()
+                .filter(|term| terms.contains(term))
+                .collect::<Vec<_>>();
+            if !matched.is_empty() {
+                tag_terms.insert(tag.id, matched);
+            }
+        }
+        if !tag_terms.is_empty() {
+            let sql = format!("SELECT article_id, tag_id FROM blog.article_tag JOIN blog.articles ON id = article_id WHERE tag_id = ANY($1) AND {};", visible);
+            let rows = sqlx::query(&sql)
+                .bind(tag_terms.keys().cloned().collect::<Vec<ID>>())
+                .fetch_all(self.deref())
+                .await?;
+            for row in rows {
+                for term in &tag_terms[&row.get::<ID, _>(1)] {
+                    matches.push((row.get(0), term.clone(), search::TAG_WEIGHT));
+                }
+            }
+        }
+
+        // step3: rank, then fetch the articles of this page
+        search::hits(self, &req, &terms, &matches, total_articles).await
+    }
+}
+
+impl DBPool {
+    /// Re-index every article, for the rows written before blog.article_terms existed.
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        let mut tx = self.begin().await?;
+
+        let sql = "SELECT id, title, summary, content FROM blog.articles;";
+        let rows = sqlx::query(sql).fetch_all(&mut tx).await?;
+        for row in rows {
+            writes::index_article(&mut tx, row.get(0), row.get(1), row.get(2), row.get(3)).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
     }
 }
@@ -0,0 +1,1402 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Local, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+
+use util_pb::batch_operation::Operation;
+use util_pb::create_request::Create;
+use util_pb::delete_request::Delete;
+use util_pb::transfer::{
+    calendar_range, check_calendar, check_mask, parse_date, to_chrono, Pagination, ARTICLE_FIELDS,
+    AS, CATEGORY_FIELDS,
+};
+use util_pb::update_request::Update;
+use util_pb::{
+    get_summary, to_timestamp, ArchiveBucket, ArchiveRequest, Article, ArticleProjection,
+    ArticleRevision, ArticleState, BatchOperation, Category, FieldMask, QueryArticle,
+    QueryCategory, QueryTag, SearchRequest, SearchResponse, Tag, TagMatch, Timestamp, Trash,
+    Visibility,
+};
+
+use crate::error::Result;
+use crate::search;
+use crate::storage::traits::{BlogDB, ID};
+use crate::storage::writes::{
+    alias_taken, ensure_not_default, parse_timezone, touches_name, unscheduled,
+};
+use crate::BackendError;
+
+const ARTICLES: &str = "blog.articles";
+const CATEGORIES: &str = "blog.categories";
+const TAGS: &str = "blog.tags";
+
+/// A `BlogDB` kept in memory, for the tests and demos that run without Postgres.
+///
+/// A write works on a copy of the tables which replaces them once it succeeded, so a failed one
+/// leaves nothing behind, as its rolled back transaction would.
+///
+/// Titles sort by their bytes rather than by a collation as in Postgres, so the order of titles
+/// differing in case or outside ASCII is not the same.
+#[derive(Clone)]
+pub struct MemoryDB {
+    tables: Arc<RwLock<Tables>>,
+}
+
+impl MemoryDB {
+    /// Nothing but category 0 and tag 0, which are always there.
+    pub fn new() -> Self {
+        Self::from_tables(Tables::new())
+    }
+
+    /// The rows the migrations seed: categories 0 and 1, tags 0 to 2, and articles 1000 and 1001.
+    pub fn sample() -> Self {
+        let mut tables = Tables::new();
+        let now = now();
+
+        tables.categories.insert(
+            1,
+            Stored::new(Category {
+                id: 1,
+                name: "Test_category1".to_string(),
+                ..Category::default()
+            }),
+        );
+        tables.last_category_id = 1;
+
+        for (id, name) in [(1, "Test_tag1"), (2, "Test_tag2")] {
+            let tag = Tag {
+                id,
+                name: name.to_string(),
+                ..Tag::default()
+            };
+            tables.tags.insert(id, Stored::new(tag));
+        }
+        tables.last_tag_id = 2;
+
+        // inserted with their ids, so the sequence of blog.articles still starts at 1
+        for (id, title, content, summary) in [
+            (1000, "test_title", "test_content", "test_summary"),
+            (1001, "test_title1", "test_content1", "test_summary1"),
+        ] {
+            let article = Article {
+                id,
+                title: title.to_string(),
+                content: content.to_string(),
+                summary: summary.to_string(),
+                state: ArticleState::Published as i32,
+                created_at: Some(now.clone()),
+                updated_at: Some(now.clone()),
+                category_id: 1,
+                version: 1,
+                ..Article::default()
+            };
+            tables.articles.insert(id, Stored::new(article));
+        }
+        tables.article_tag = vec![(1000, 1), (1000, 2), (1001, 2)];
+
+        Self::from_tables(tables)
+    }
+
+    fn from_tables(tables: Tables) -> Self {
+        Self {
+            tables: Arc::new(RwLock::new(tables)),
+        }
+    }
+
+    // the tables are only ever replaced whole, so a panic holding the lock cannot leave them half
+    // written and a poisoned lock is still fine to use
+    fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `write` on a copy of the tables, kept only if it succeeds.
+    fn write<T>(&self, write: impl FnOnce(&mut Tables) -> Result<T>) -> Result<T> {
+        self.write_from(|_, copy| write(copy))
+    }
+
+    /// `write`, also given the tables as they were before it.
+    fn write_from<T>(&self, write: impl FnOnce(&Tables, &mut Tables) -> Result<T>) -> Result<T> {
+        let mut tables = self.tables.write().unwrap_or_else(PoisonError::into_inner);
+        let mut copy = tables.clone();
+        let res = write(&tables, &mut copy)?;
+        *tables = copy;
+        Ok(res)
+    }
+}
+
+impl Default for MemoryDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tonic::async_trait]
+impl BlogDB for MemoryDB {
+    async fn query_articles(&self, req: QueryArticle) -> Result<Vec<Article>> {
+        check_calendar(&req).map_err(BackendError::InvalidRequest)?;
+        let pagination = Pagination::from_query(&req).map_err(BackendError::InvalidRequest)?;
+        let tables = self.read();
+
+        let mut articles = tables
+            .matching(&req)?
+            .into_iter()
+            .filter(|article| pagination.is_after(article))
+            .collect::<Vec<_>>();
+        articles.sort_by(|a, b| pagination.compare(a, b));
+        if let Some(limit) = pagination.limit() {
+            articles.truncate(limit);
+        }
+
+        let res = articles
+            .into_iter()
+            .map(|article| tables.output(article, req.projection))
+            .collect();
+        Ok(res)
+    }
+
+    async fn count_articles(&self, req: QueryArticle) -> Result<i64> {
+        check_calendar(&req).map_err(BackendError::InvalidRequest)?;
+        let total = self.read().matching(&req)?.len();
+        Ok(total as i64)
+    }
+
+    async fn add_article(&self, article: Article) -> Result<ID> {
+        self.write(|tables| tables.add_article(article))
+    }
+
+    async fn edit_article(&self, article: Article, mask: Option<FieldMask>) -> Result<ID> {
+        self.write(|tables| tables.edit_article(article, mask))
+    }
+
+    async fn delete_article(&self, id: ID) -> Result<()> {
+        self.write(|tables| move_to_trash(&mut tables.articles, ARTICLES, id, true))
+    }
+
+    async fn restore_article(&self, id: ID) -> Result<()> {
+        self.write(|tables| move_to_trash(&mut tables.articles, ARTICLES, id, false))
+    }
+
+    async fn purge_article(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.purge_article(id))
+    }
+
+    async fn list_revisions(&self, article_id: ID) -> Result<Vec<ArticleRevision>> {
+        let res = self
+            .read()
+            .revisions
+            .iter()
+            .rev()
+            .filter(|revision| revision.article_id == article_id)
+            .cloned()
+            .collect();
+        Ok(res)
+    }
+
+    async fn restore_revision(&self, revision_id: ID) -> Result<ID> {
+        self.write(|tables| tables.restore_revision(revision_id))
+    }
+
+    async fn query_categories(&self, req: QueryCategory) -> Result<Vec<Category>> {
+        let tables = self.read();
+        let res = tables
+            .categories
+            .values()
+            .filter(|row| in_view(req.trash, row.in_trash))
+            .map(|row| &row.value)
+            .filter(|category| req.ids.is_empty() || req.ids.contains(&category.id))
+            .filter(|category| category.name.contains(&req.name))
+            .map(|category| tables.count_category(category))
+            .collect();
+        Ok(res)
+    }
+
+    async fn add_category(&self, category: Category) -> Result<ID> {
+        self.write(|tables| tables.add_category(category))
+    }
+
+    async fn edit_category(&self, category: Category, mask: Option<FieldMask>) -> Result<ID> {
+        self.write(|tables| tables.edit_category(category, mask))
+    }
+
+    async fn delete_category(&self, id: ID, reassign_to: Option<ID>) -> Result<()> {
+        self.write(|tables| tables.delete_category(id, reassign_to))
+    }
+
+    async fn restore_category(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.restore_category(id))
+    }
+
+    async fn purge_category(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.purge_category(id))
+    }
+
+    async fn query_tags(&self, req: QueryTag) -> Result<Vec<Tag>> {
+        let tables = self.read();
+        let res = tables
+            .tags
+            .values()
+            .filter(|row| in_view(req.trash, row.in_trash))
+            .map(|row| &row.value)
+            .filter(|tag| req.ids.is_empty() || req.ids.contains(&tag.id))
+            .filter(|tag| tag.name.contains(&req.name))
+            .map(|tag| tables.count_tag(tag))
+            .collect();
+        Ok(res)
+    }
+
+    async fn add_tag(&self, tag: Tag) -> Result<ID> {
+        self.write(|tables| tables.add_tag(tag))
+    }
+
+    async fn edit_tag(&self, tag: Tag, mask: Option<FieldMask>) -> Result<ID> {
+        self.write(|tables| tables.edit_tag(tag, mask))
+    }
+
+    async fn delete_tag(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.delete_tag(id))
+    }
+
+    async fn restore_tag(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.restore_tag(id))
+    }
+
+    async fn purge_tag(&self, id: ID) -> Result<()> {
+        self.write(|tables| tables.purge_tag(id))
+    }
+
+    async fn merge_tags(&self, source_ids: Vec<ID>, target_id: ID) -> Result<i32> {
+        self.write(|tables| tables.merge_tags(source_ids, target_id))
+    }
+
+    async fn resolve_tags(&self, names: Vec<String>) -> Result<Vec<ID>> {
+        self.write(|tables| {
+            let mut ids = Vec::with_capacity(names.len());
+            for name in names.iter().map(|name| name.trim()) {
+                if name.is_empty() {
+                    continue;
+                }
+                let id = tables.resolve_tag(name)?;
+                // `Rust, rust` or a name and its alias
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            Ok(ids)
+        })
+    }
+
+    async fn find_tags(&self, names: Vec<String>) -> Result<(Vec<ID>, Vec<String>)> {
+        let tables = self.read();
+        let mut ids = Vec::with_capacity(names.len());
+        let mut unknown = vec![];
+        for name in names.iter().map(|name| name.trim()) {
+            if name.is_empty() {
+                continue;
+            }
+            match tables.find_tag(name) {
+                Some(id) if !ids.contains(&id) => ids.push(id),
+                Some(_) => {}
+                None => unknown.push(name.to_string()),
+            }
+        }
+        Ok((ids, unknown))
+    }
+
+    async fn tag_to_articles(&self, tag_id: ID) -> Result<Vec<ID>> {
+        let tables = self.read();
+        let res = tables
+            .article_tag
+            .iter()
+            .filter(|(article_id, id)| *id == tag_id && !tables.articles[article_id].in_trash)
+            .map(|(article_id, _)| *article_id)
+            .collect::<Vec<_>>();
+        // no row of blog.tag2article for a tag without articles
+        if res.is_empty() {
+            return Err(not_found());
+        }
+        Ok(res)
+    }
+
+    async fn article_to_tags(&self, article_id: ID) -> Result<Vec<ID>> {
+        Ok(self.read().tags_of(article_id))
+    }
+
+    async fn archive(&self, req: ArchiveRequest) -> Result<Vec<ArchiveBucket>> {
+        let timezone = parse_timezone(&req.timezone)?;
+        let tables = self.read();
+
+        let mut counts = BTreeMap::<(i32, i32), i32>::new();
+        for article in tables.visible(req.visibility) {
+            let local = created_at(article).with_timezone(&timezone);
+            *counts
+                .entry((local.year(), local.month() as i32))
+                .or_default() += 1;
+        }
+        let buckets = counts
+            .into_iter()
+            .rev()
+            .map(|((year, month), count)| ArchiveBucket { year, month, count })
+            .collect();
+        Ok(buckets)
+    }
+
+    async fn batch(&self, operations: Vec<BatchOperation>) -> Result<Vec<Result<ID>>> {
+        // rather than each operation copying the tables for its savepoint, a failed one goes back
+        // to the tables before the batch and replays the operations that succeeded
+        self.write_from(|before, tables| {
+            let mut applied = vec![];
+            let mut results = Vec::with_capacity(operations.len());
+            for operation in operations {
+                let res = tables.apply(operation.clone());
+                match res {
+                    Ok(_) => applied.push(operation),
+                    Err(_) => {
+                        *tables = before.clone();
+                        for operation in &applied {
+                            tables.apply(operation.clone())?;
+                        }
+                    }
+                }
+                results.push(res);
+            }
+            Ok(results)
+        })
+    }
+
+    async fn publish_due(&self, now: DateTime<Local>) -> Result<Vec<ID>> {
+        self.write(|tables| {
+            let mut ids = vec![];
+            for row in tables.articles.values_mut() {
+                let article = &mut row.value;
+                let due = article.publish_at.as_ref().map(to_chrono) <= Some(now);
+                if article.state == ArticleState::Scheduled as i32
+                    && article.publish_at.is_some()
+                    && due
+                {
+                    article.state = ArticleState::Published as i32;
+                    article.version += 1;
+                    ids.push(article.id);
+                }
+            }
+            Ok(ids)
+        })
+    }
+
+    async fn expire_due(&self, now: DateTime<Local>) -> Result<Vec<ID>> {
+        self.write(|tables| {
+            let mut ids = vec![];
+            for row in tables.articles.values_mut() {
+                let article = &mut row.value;
+                let due = article.expire_at.as_ref().map(to_chrono) <= Some(now);
+                if article.state == ArticleState::Published as i32
+                    && article.expire_at.is_some()
+                    && due
+                {
+                    article.state = ArticleState::Hidden as i32;
+                    article.expire_at = None;
+                    article.version += 1;
+                    ids.push(article.id);
+                }
+            }
+            Ok(ids)
+        })
+    }
+
+    async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
+        if req.page < 0 || req.page_size < 0 {
+            return Err(BackendError::InvalidRequest(
+                "Negative search page.".to_string(),
+            ));
+        }
+        let terms = search::query_terms(&req.query);
+        if terms.is_empty() {
+            return Ok(SearchResponse::default());
+        }
+
+        let (matches, total_articles) = {
+            let tables = self.read();
+
+            // step0: the articles this request may see
+            let visible = tables
+                .visible(req.visibility)
+                .map(|article| (article.id, article))
+                .collect::<BTreeMap<_, _>>();
+
+            // step1: title, summary and content
+            let mut matches = vec![];
+            for article in visible.values() {
+                let weights =
+                    search::term_weights(&article.title, &article.summary, &article.content);
+                for (term, weight) in weights {
+                    if terms.contains(&term) {
+                        matches.push((article.id, term, weight));
+                    }
+                }
+            }
+
+            // step2: tag names
+            for tag in tables.tags.values().filter(|row| !row.in_trash) {
+                let matched = search::tokenize(&tag.value.name)
+                    .into_iter()
+                    .filter(|term| terms.contains(term))
+                    .collect::<Vec<_>>();
+                for (article_id, _) in tables.article_tag.iter().filter(|(article_id, tag_id)| {
+                    *tag_id == tag.value.id && visible.contains_key(article_id)
+                }) {
+                    for term in &matched {
+                        matches.push((*article_id, term.clone(), search::TAG_WEIGHT));
+                    }
+                }
+            }
+            (matches, visible.len() as i64)
+        };
+
+        // step3: rank, then fetch the articles of this page
+        search::hits(self, &req, &terms, &matches, total_articles).await
+    }
+}
+
+/* =================================================================
+
+
+Tables
+
+
+================================================================== */
+/// A row and whether it is in the trash bin.
+#[derive(Clone)]
+struct Stored<T> {
+    value: T,
+    in_trash: bool,
+}
+
+impl<T> Stored<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            in_trash: false,
+        }
+    }
+}
+
+/// The tables of the migrations; the counts of categories and tags and the `tags_id` of articles
+/// are filled on the way out.
+#[derive(Clone)]
+struct Tables {
+    articles: BTreeMap<ID, Stored<Article>>,
+    categories: BTreeMap<ID, Stored<Category>>,
+    tags: BTreeMap<ID, Stored<Tag>>,
+    /// (article_id, tag_id) in the order they were linked
+    article_tag: Vec<(ID, ID)>,
+    revisions: Vec<ArticleRevision>,
+    tag_aliases: HashMap<String, ID>,
+    // the last ids handed out, as the sequences of the SERIAL columns
+    last_article_id: ID,
+    last_category_id: ID,
+    last_tag_id: ID,
+    last_revision_id: ID,
+}
+
+impl Tables {
+    fn new() -> Self {
+        let category = Category {
+            id: 0,
+            name: "undefined-category".to_string(),
+            ..Category::default()
+        };
+        let tag = Tag {
+            id: 0,
+            name: "undefined-tag".to_string(),
+            ..Tag::default()
+        };
+        Self {
+            articles: BTreeMap::new(),
+            categories: BTreeMap::from([(0, Stored::new(category))]),
+            tags: BTreeMap::from([(0, Stored::new(tag))]),
+            article_tag: vec![],
+            revisions: vec![],
+            tag_aliases: HashMap::new(),
+            last_article_id: 0,
+            last_category_id: 0,
+            last_tag_id: 0,
+            last_revision_id: 0,
+        }
+    }
+
+    /// One operation of a batch, as the Create, Update and Delete RPCs would do it.
+    fn apply(&mut self, operation: BatchOperation) -> Result<ID> {
+        let empty = || BackendError::InvalidRequest("Empty batch operation.".to_string());
+        match operation.operation.ok_or_else(empty)? {
+            Operation::Create(req) => match req.create.ok_or_else(empty)? {
+                Create::Article(article) => self.add_article(article),
+                Create::Category(category) => self.add_category(category),
+                Create::Tag(tag) => self.add_tag(tag),
+            },
+            Operation::Update(req) => match req.update.ok_or_else(empty)? {
+                Update::Article(article) => self.edit_article(article, req.update_mask),
+                Update::Category(category) => self.edit_category(category, req.update_mask),
+                Update::Tag(tag) => self.edit_tag(tag, req.update_mask),
+            },
+            Operation::Delete(req) => match req.delete.ok_or_else(empty)? {
+                Delete::ArticleId(id) if req.purge => self.purge_article(id).map(|_| id),
+                Delete::ArticleId(id) => {
+                    move_to_trash(&mut self.articles, ARTICLES, id, true).map(|_| id)
+                }
+                Delete::CategoryId(id) if req.purge => self.purge_category(id).map(|_| id),
+                Delete::CategoryId(id) => self.delete_category(id, req.reassign_to).map(|_| id),
+                Delete::TagId(id) if req.purge => self.purge_tag(id).map(|_| id),
+                Delete::TagId(id) => self.delete_tag(id).map(|_| id),
+            },
+        }
+    }
+
+    /// The articles passing the filters of `req`, in no particular order.
+    fn matching(&self, req: &QueryArticle) -> Result<Vec<&Article>> {
+        let now = Local::now();
+        let states = req
+            .state_filter
+            .as_ref()
+            .map(|filter| filter.states.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter(|state| AS::try_from(**state).is_ok())
+            .collect::<HashSet<_>>();
+        let created = CreatedRange::from_query(req)?;
+        let categories = match (req.category_id, req.include_descendants) {
+            (0, _) => None,
+            (id, true) => Some(self.subtree(id)),
+            (id, false) => Some(HashSet::from([id])),
+        };
+        let mut tags_id = req.tags_id.clone();
+        tags_id.sort_unstable();
+        tags_id.dedup();
+
+        let res = self
+            .articles
+            .values()
+            .filter(|row| in_view(req.trash, row.in_trash))
+            .map(|row| &row.value)
+            .filter(|article| req.ids.is_empty() || req.ids.contains(&article.id))
+            .filter(|article| article.title.contains(&req.title))
+            .filter(|article| tags_id.is_empty() || self.tag_match(article.id, &tags_id, req))
+            .filter(|article| states.is_empty() || states.contains(&article.state))
+            .filter(|article| created.contains(created_at(article)))
+            .filter(|article| match &categories {
+                Some(categories) => categories.contains(&article.category_id),
+                None => true,
+            })
+            .filter(|article| match Visibility::from_i32(req.visibility) {
+                Some(Visibility::Public) => is_public(article, now),
+                _ => true,
+            })
+            .collect();
+        Ok(res)
+    }
+
+    /// Whether the links of an article, those to tags in the trash bin included, match `tags_id`.
+    fn tag_match(&self, article_id: ID, tags_id: &[ID], req: &QueryArticle) -> bool {
+        let linked = self
+            .article_tag
+            .iter()
+            .filter(|(id, tag_id)| *id == article_id && tags_id.contains(tag_id))
+            .count();
+        match TagMatch::from_i32(req.tag_match) {
+            Some(TagMatch::Any) => linked > 0,
+            Some(TagMatch::None) => linked == 0,
+            _ => linked == tags_id.len(),
+        }
+    }
+
+    /// The articles out of the trash bin a request of `visibility` may see.
+    fn visible(&self, visibility: i32) -> impl Iterator<Item = &Article> {
+        let now = Local::now();
+        let public = matches!(Visibility::from_i32(visibility), Some(Visibility::Public));
+        self.articles
+            .values()
+            .filter(|row| !row.in_trash)
+            .map(|row| &row.value)
+            .filter(move |article| !public || is_public(article, now))
+    }
+
+    /// An article as `query_articles` returns it.
+    fn output(&self, article: &Article, projection: i32) -> Article {
+        let mut article = article.clone();
+        article.tags_id = self.tags_of(article.id);
+        if let Some(ArticleProjection::Summary) = ArticleProjection::from_i32(projection) {
+            article.content = String::new();
+        }
+        article
+    }
+
+    /// Tags of an article out of the trash bin, as blog.article2tag has them.
+    fn tags_of(&self, article_id: ID) -> Vec<ID> {
+        self.article_tag
+            .iter()
+            .filter(|(id, tag_id)| *id == article_id && !self.tags[tag_id].in_trash)
+            .map(|(_, tag_id)| *tag_id)
+            .collect()
+    }
+
+    fn add_article(&mut self, article: Article) -> Result<ID> {
+        let summary = if article.summary.is_empty() {
+            get_summary(&article.content)
+        } else {
+            article.summary
+        };
+
+        let state = match ArticleState::from_i32(article.state) {
+            Some(ArticleState::Unspecified) => AS::Draft,
+            _ => AS::try_from(article.state).map_err(BackendError::InvalidRequest)?,
+        };
+        if matches!(state, AS::Scheduled) && article.publish_at.is_none() {
+            return Err(unscheduled());
+        }
+        self.ensure_category(article.category_id)?;
+
+        // step1: blog.articles
+        self.last_article_id += 1;
+        let id = self.last_article_id;
+        let now = now();
+        let stored = Article {
+            id,
+            title: article.title,
+            content: article.content,
+            summary,
+            state: ArticleState::from(state) as i32,
+            created_at: Some(now.clone()),
+            updated_at: Some(now),
+            category_id: article.category_id,
+            tags_id: vec![],
+            publish_at: article.publish_at,
+            expire_at: article.expire_at,
+            version: 1,
+        };
+        self.articles.insert(id, Stored::new(stored));
+
+        // step2: blog.article_tag
+        self.link_tags(id, &article.tags_id)?;
+        self.fall_back_to_default_tag(&[id]);
+
+        Ok(id)
+    }
+
+    fn edit_article(&mut self, article: Article, mask: Option<FieldMask>) -> Result<ID> {
+        let paths = mask.map(|mask| mask.paths);
+        if let Some(paths) = &paths {
+            check_mask(paths, &ARTICLE_FIELDS).map_err(BackendError::InvalidRequest)?;
+        }
+        let touches = |field: &str| match &paths {
+            Some(paths) => paths.iter().any(|path| path == field),
+            None => field != "state" || article.state != ArticleState::Unspecified as i32,
+        };
+
+        let id = article.id;
+        let mut edited = self.articles.get(&id).ok_or_else(not_found)?.value.clone();
+        if article.version != 0 && article.version != edited.version {
+            return Err(BackendError::Conflict(format!(
+                "The article was edited in the meantime, version {} is now {}.",
+                article.version, edited.version
+            )));
+        }
+
+        if touches("state") {
+            let from = AS::try_from(edited.state).map_err(BackendError::InvalidRequest)?;
+            let to = AS::try_from(article.state).map_err(BackendError::InvalidRequest)?;
+            if !from.can_become(to) {
+                return Err(BackendError::InvalidRequest(format!(
+                    "An article cannot go from {} to {}.",
+                    from.name(),
+                    to.name()
+                )));
+            }
+        }
+
+        // step0: keep the current version
+        self.last_revision_id += 1;
+        self.revisions.push(ArticleRevision {
+            id: self.last_revision_id,
+            article_id: id,
+            title: edited.title.clone(),
+            content: edited.content.clone(),
+            summary: edited.summary.clone(),
+            state: edited.state,
+            category_id: edited.category_id,
+            tags_id: self
+                .article_tag
+                .iter()
+                .filter(|(article_id, _)| *article_id == id)
+                .map(|(_, tag_id)| *tag_id)
+                .collect(),
+            created_at: Some(now()),
+        });
+
+        // step1, the listed fields as they are, or else the non-empty ones
+        let tags_id = article.tags_id.clone();
+        let touches_tags = touches("tags_id");
+        match &paths {
+            Some(_) => {
+                if touches("title") {
+                    edited.title = article.title;
+                }
+                if touches("content") {
+                    edited.content = article.content;
+                }
+                if touches("summary") {
+                    edited.summary = article.summary;
+                }
+                if touches("state") {
+                    edited.state = article.state;
+                }
+                if touches("category_id") {
+                    edited.category_id = article.category_id;
+                }
+                if touches("publish_at") {
+                    edited.publish_at = article.publish_at;
+                }
+                if touches("expire_at") {
+                    edited.expire_at = article.expire_at;
+                }
+            }
+            None => {
+                if !article.summary.is_empty() {
+                    edited.summary = article.summary;
+                } else if !article.content.is_empty() {
+                    edited.summary = get_summary(&article.content);
+                }
+                if !article.title.is_empty() {
+                    edited.title = article.title;
+                }
+                if !article.content.is_empty() {
+                    edited.content = article.content;
+                }
+                if AS::try_from(article.state).is_ok() {
+                    edited.state = article.state;
+                }
+                if article.category_id != 0 {
+                    edited.category_id = article.category_id;
+                }
+                if article.publish_at.is_some() {
+                    edited.publish_at = article.publish_at;
+                }
+                if article.expire_at.is_some() {
+                    edited.expire_at = article.expire_at;
+                }
+            }
+        }
+        self.ensure_category(edited.category_id)?;
+        if edited.state == ArticleState::Scheduled as i32 && edited.publish_at.is_none() {
+            return Err(unscheduled());
+        }
+        edited.updated_at = Some(now());
+        edited.version += 1;
+        self.articles.insert(id, Stored::new(edited));
+
+        if !touches_tags {
+            return Ok(id);
+        }
+
+        // step2, leaving the links to the tags in the trash bin alone
+        let old_tags = self.tags_of(id);
+        let need_add_tags = tags_id
+            .iter()
+            .filter(|tag_id| !old_tags.contains(tag_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.link_tags(id, &need_add_tags)?;
+        self.article_tag.retain(|(article_id, tag_id)| {
+            *article_id != id || !old_tags.contains(tag_id) || tags_id.contains(tag_id)
+        });
+        self.fall_back_to_default_tag(&[id]);
+
+        Ok(id)
+    }
+
+    fn purge_article(&mut self, id: ID) -> Result<()> {
+        ensure_in_trash(&self.articles, ARTICLES, id)?;
+
+        self.article_tag.retain(|(article_id, _)| *article_id != id);
+        self.revisions.retain(|revision| revision.article_id != id);
+        self.articles.remove(&id);
+
+        Ok(())
+    }
+
+    fn restore_revision(&mut self, revision_id: ID) -> Result<ID> {
+        let revision = self
+            .revisions
+            .iter()
+            .find(|revision| revision.id == revision_id)
+            .cloned()
+            .ok_or_else(|| {
+                BackendError::InvalidRequest(format!("No such revision: {}", revision_id))
+            })?;
+
+        let article = Article {
+            id: revision.article_id,
+            title: revision.title,
+            content: revision.content,
+            summary: revision.summary,
+            state: revision.state,
+            category_id: revision.category_id,
+            tags_id: revision.tags_id,
+            ..Article::default()
+        };
+        let mask = FieldMask {
+            paths: ["title", "content", "summary", "category_id", "tags_id"]
+                .map(String::from)
+                .to_vec(),
+        };
+        self.edit_article(article, Some(mask))
+    }
+
+    /// Link an article to `tags_id`, keeping the links it already has.
+    fn link_tags(&mut self, article_id: ID, tags_id: &[ID]) -> Result<()> {
+        for &tag_id in tags_id {
+            if !self.tags.contains_key(&tag_id) {
+                return Err(BackendError::InvalidRequest(format!(
+                    "No such tag: {}",
+                    tag_id
+                )));
+            }
+            if !self.article_tag.contains(&(article_id, tag_id)) {
+                self.article_tag.push((article_id, tag_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Give tag 0 to those of `article_ids` left without any tag out of the trash bin.
+    fn fall_back_to_default_tag(&mut self, article_ids: &[ID]) {
+        for &id in article_ids {
+            let tagged = !self.tags_of(id).is_empty();
+            if !tagged && self.articles.contains_key(&id) && !self.article_tag.contains(&(id, 0)) {
+                self.article_tag.push((id, 0));
+            }
+        }
+    }
+
+    /// A category with the articles of its subtree counted.
+    fn count_category(&self, category: &Category) -> Category {
+        let subtree = self.subtree(category.id);
+        let (published_count, total_count) =
+            self.count(|article| subtree.contains(&article.category_id));
+        Category {
+            published_count,
+            total_count,
+            ..category.clone()
+        }
+    }
+
+    /// Ids of a category and all its subcategories, stopping on a cycle.
+    fn subtree(&self, category_id: ID) -> HashSet<ID> {
+        let mut subtree = HashSet::new();
+        if !self.categories.contains_key(&category_id) {
+            return subtree;
+        }
+        let mut pending = vec![category_id];
+        while let Some(id) = pending.pop() {
+            if subtree.insert(id) {
+                pending.extend(
+                    self.categories
+                        .values()
+                        .filter(|row| row.value.parent_id == Some(id))
+                        .map(|row| row.value.id),
+                );
+            }
+        }
+        subtree
+    }
+
+    fn ensure_category(&self, id: ID) -> Result<()> {
+        if !self.categories.contains_key(&id) {
+            return Err(BackendError::InvalidRequest(format!(
+                "No such category: {}",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Take the category out of the trash bin, unless its name was given to another one meanwhile.
+    fn restore_category(&mut self, id: ID) -> Result<()> {
+        if let Some(row) = self.categories.get(&id).filter(|row| row.in_trash) {
+            self.ensure_category_name_free(&row.value.name, Some(id))?;
+        }
+        move_to_trash(&mut self.categories, CATEGORIES, id, false)
+    }
+
+    fn ensure_category_name_free(&self, name: &str, id: Option<ID>) -> Result<()> {
+        let taken = self
+            .categories
+            .values()
+            .any(|row| !row.in_trash && row.value.name == name && Some(row.value.id) != id);
+        if taken {
+            return Err(name_taken(CATEGORIES, name));
+        }
+        Ok(())
+    }
+
+    fn add_category(&mut self, category: Category) -> Result<ID> {
+        self.ensure_category_name_free(&category.name, None)?;
+        if let Some(parent_id) = category.parent_id {
+            self.ensure_category(parent_id)?;
+        }
+
+        self.last_category_id += 1;
+        let id = self.last_category_id;
+        let stored = Category {
+            id,
+            name: category.name,
+            parent_id: category.parent_id,
+            ..Category::default()
+        };
+        self.categories.insert(id, Stored::new(stored));
+        Ok(id)
+    }
+
+    fn edit_category(&mut self, category: Category, mask: Option<FieldMask>) -> Result<ID> {
+        let paths = mask.map(|mask| mask.paths);
+        if let Some(paths) = &paths {
+            check_mask(paths, &CATEGORY_FIELDS).map_err(BackendError::InvalidRequest)?;
+        }
+        let touches = |field: &str| match &paths {
+            Some(paths) => paths.iter().any(|path| path == field),
+            None => field == "name" || category.parent_id.is_some(),
+        };
+
+        if touches("name") || touches("parent_id") {
+            ensure_not_default(CATEGORIES, category.id, "edited")?;
+        }
+
+        if let (true, Some(parent_id)) = (touches("parent_id"), category.parent_id) {
+            self.ensure_category(parent_id)?;
+            self.ensure_acyclic(category.id, parent_id)?;
+        }
+
+        if !self.categories.contains_key(&category.id) {
+            return Err(not_found());
+        }
+        if touches("name") {
+            self.ensure_category_name_free(&category.name, Some(category.id))?;
+        }
+        let stored = &mut self.categories.get_mut(&category.id).unwrap().value;
+        if touches("name") {
+            stored.name = category.name;
+        }
+        if touches("parent_id") {
+            stored.parent_id = category.parent_id;
+        }
+
+        Ok(category.id)
+    }
+
+    /// Fail if `parent_id` is `id` or one of its subcategories.
+    fn ensure_acyclic(&self, id: ID, parent_id: ID) -> Result<()> {
+        let mut ancestors = HashSet::new();
+        let mut next = Some(parent_id);
+        while let Some(ancestor) = next.filter(|ancestor| ancestors.insert(*ancestor)) {
+            if ancestor == id {
+                return Err(BackendError::InvalidRequest(format!(
+                    "Category {} cannot be moved under {}, one of its own subcategories.",
+                    id, parent_id
+                )));
+            }
+            next = self
+                .categories
+                .get(&ancestor)
+                .and_then(|row| row.value.parent_id);
+        }
+        Ok(())
+    }
+
+    fn delete_category(&mut self, id: ID, reassign_to: Option<ID>) -> Result<()> {
+        ensure_not_default(CATEGORIES, id, "deleted")?;
+        let reassign_to = reassign_to.unwrap_or(0);
+
+        let available = self
+            .categories
+            .get(&reassign_to)
+            .is_some_and(|row| !row.in_trash && reassign_to != id);
+        if !available {
+            return Err(BackendError::InvalidRequest(format!(
+                "Cannot move the articles of category {} to {}.",
+                id, reassign_to
+            )));
+        }
+        for row in self.articles.values_mut() {
+            if row.value.category_id == id {
+                row.value.category_id = reassign_to;
+            }
+        }
+        move_to_trash(&mut self.categories, CATEGORIES, id, true)
+    }
+
+    fn purge_category(&mut self, id: ID) -> Result<()> {
+        ensure_in_trash(&self.categories, CATEGORIES, id)?;
+
+        // articles given the category while it was in the trash bin
+        for row in self.articles.values_mut() {
+            if row.value.category_id == id {
+                row.value.category_id = 0;
+            }
+        }
+        self.categories.remove(&id);
+        // the children become roots
+        for row in self.categories.values_mut() {
+            if row.value.parent_id == Some(id) {
+                row.value.parent_id = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A tag with its articles counted.
+    fn count_tag(&self, tag: &Tag) -> Tag {
+        let (published_count, total_count) =
+            self.count(|article| self.article_tag.contains(&(article.id, tag.id)));
+        Tag {
+            published_count,
+            total_count,
+            ..tag.clone()
+        }
+    }
+
+    /// The tag `name` stands for, ignoring case: a tag of that name, or the one an alias was merged
+    /// into. The exact spelling wins over the others, then a tag over an alias.
+    fn find_tag(&self, name: &str) -> Option<ID> {
+        let lower = name.to_lowercase();
+        let tags = self
+            .tags
+            .values()
+            .filter(|row| !row.in_trash && row.value.name.to_lowercase() == lower)
+            .map(|row| (row.value.name != name, 0, row.value.id));
+        let aliases = self
+            .tag_aliases
+            .iter()
+            .filter(|(alias, _)| alias.to_lowercase() == lower)
+            .map(|(alias, tag_id)| (alias != name, 1, *tag_id));
+        tags.chain(aliases).min().map(|(_, _, id)| id)
+    }
+
+    fn ensure_tag_name_free(&self, name: &str, id: Option<ID>) -> Result<()> {
+        let taken = self
+            .tags
+            .values()
+            .any(|row| !row.in_trash && row.value.name == name && Some(row.value.id) != id);
+        if taken {
+            return Err(name_taken(TAGS, name));
+        }
+        Ok(())
+    }
+
+    fn insert_tag(&mut self, name: &str) -> Result<ID> {
+        self.ensure_tag_name_free(name, None)?;
+
+        self.last_tag_id += 1;
+        let id = self.last_tag_id;
+        let tag = Tag {
+            id,
+            name: name.to_string(),
+            ..Tag::default()
+        };
+        self.tags.insert(id, Stored::new(tag));
+        Ok(id)
+    }
+
+    fn add_tag(&mut self, tag: Tag) -> Result<ID> {
+        // another spelling of a tag, or the name of one merged into it, stands for that tag
+        let name = tag.name.trim();
+        if name.is_empty() {
+            return Err(BackendError::InvalidRequest(
+                "A tag needs a name.".to_string(),
+            ));
+        }
+        self.resolve_tag(name)
+    }
+
+    /// The tag `name` stands for as `find_tag` finds it, created when there is none.
+    fn resolve_tag(&mut self, name: &str) -> Result<ID> {
+        match self.find_tag(name) {
+            Some(id) => Ok(id),
+            None => self.insert_tag(name),
+        }
+    }
+
+    fn edit_tag(&mut self, tag: Tag, mask: Option<FieldMask>) -> Result<ID> {
+        if !touches_name(mask)? {
+            return match self.tags.contains_key(&tag.id) {
+                true => Ok(tag.id),
+                false => Err(not_found()),
+            };
+        }
+
+        ensure_not_default(TAGS, tag.id, "renamed")?;
+
+        if !self.tags.contains_key(&tag.id) {
+            return Err(not_found());
+        }
+        self.ensure_tag_name_free(&tag.name, Some(tag.id))?;
+        // the name of a tag merged into another stands for that one
+        let lower = tag.name.to_lowercase();
+        let alias = self
+            .tag_aliases
+            .iter()
+            .find(|(alias, tag_id)| alias.to_lowercase() == lower && **tag_id != tag.id);
+        if let Some((_, tag_id)) = alias {
+            return Err(alias_taken(&tag.name, *tag_id));
+        }
+        self.tags.get_mut(&tag.id).unwrap().value.name = tag.name;
+        Ok(tag.id)
+    }
+
+    fn delete_tag(&mut self, id: ID) -> Result<()> {
+        ensure_not_default(TAGS, id, "deleted")?;
+        move_to_trash(&mut self.tags, TAGS, id, true)?;
+
+        let article_ids = self.articles_of(id);
+        self.fall_back_to_default_tag(&article_ids);
+        Ok(())
+    }
+
+    /// Take the tag out of the trash bin, unless its name was given to another one meanwhile, and
+    /// tag 0 off the articles it gives a tag again.
+    fn restore_tag(&mut self, id: ID) -> Result<()> {
+        if let Some(row) = self.tags.get(&id).filter(|row| row.in_trash) {
+            self.ensure_tag_name_free(&row.value.name, Some(id))?;
+        }
+        move_to_trash(&mut self.tags, TAGS, id, false)?;
+
+        let article_ids = self.articles_of(id);
+        self.article_tag.retain(|(article_id, tag_id)| {
+            *tag_id != 0 || id == 0 || !article_ids.contains(article_id)
+        });
+        Ok(())
+    }
+
+    /// Ids of the articles linked to a tag, those in the trash bin included.
+    fn articles_of(&self, tag_id: ID) -> Vec<ID> {
+        self.article_tag
+            .iter()
+            .filter(|(_, id)| *id == tag_id)
+            .map(|(article_id, _)| *article_id)
+            .collect()
+    }
+
+    fn purge_tag(&mut self, id: ID) -> Result<()> {
+        ensure_in_trash(&self.tags, TAGS, id)?;
+
+        let article_ids = self.articles_of(id);
+        self.article_tag.retain(|(_, tag_id)| *tag_id != id);
+        self.fall_back_to_default_tag(&article_ids);
+
+        self.tags.remove(&id);
+        self.tag_aliases.retain(|_, tag_id| *tag_id != id);
+
+        Ok(())
+    }
+
+    fn merge_tags(&mut self, source_ids: Vec<ID>, target_id: ID) -> Result<i32> {
+        if source_ids.is_empty() || source_ids.contains(&target_id) || source_ids.contains(&0) {
+            return Err(BackendError::InvalidRequest(format!(
+                "Cannot merge tags {:?} into {}.",
+                source_ids, target_id
+            )));
+        }
+        if self.tags.get(&target_id).is_none_or(|row| row.in_trash) {
+            return Err(BackendError::InvalidRequest(format!(
+                "No such tag: {}",
+                target_id
+            )));
+        }
+        if !source_ids.iter().all(|id| self.tags.contains_key(id)) {
+            return Err(BackendError::InvalidRequest(format!(
+                "No such tags: {:?}",
+                source_ids
+            )));
+        }
+
+        // step0: the articles, once each
+        let mut article_ids = vec![];
+        for (article_id, tag_id) in &self.article_tag {
+            if source_ids.contains(tag_id) && !article_ids.contains(article_id) {
+                article_ids.push(*article_id);
+            }
+        }
+        for article_id in &article_ids {
+            if !self.article_tag.contains(&(*article_id, target_id)) {
+                self.article_tag.push((*article_id, target_id));
+            }
+        }
+        self.article_tag
+            .retain(|(_, tag_id)| !source_ids.contains(tag_id));
+
+        // step1: the revisions, so restoring one does not bring a source back
+        for revision in &mut self.revisions {
+            let mut tags_id = vec![];
+            for tag_id in &revision.tags_id {
+                let tag_id = match source_ids.contains(tag_id) {
+                    true => target_id,
+                    false => *tag_id,
+                };
+                if !tags_id.contains(&tag_id) {
+                    tags_id.push(tag_id);
+                }
+            }
+            revision.tags_id = tags_id;
+        }
+
+        // step2: the names, those the sources were aliases for included
+        for tag_id in self.tag_aliases.values_mut() {
+            if source_ids.contains(tag_id) {
+                *tag_id = target_id;
+            }
+        }
+        for id in &source_ids {
+            if let Some(row) = self.tags.remove(id) {
+                self.tag_aliases.insert(row.value.name, target_id);
+            }
+        }
+
+        Ok(article_ids.len() as i32)
+    }
+
+    /// Public and total number of the articles out of the trash bin `pick` keeps.
+    fn count(&self, pick: impl Fn(&Article) -> bool) -> (i64, i64) {
+        let now = Local::now();
+        self.visible(Visibility::All as i32)
+            .filter(|article| pick(article))
+            .fold((0, 0), |(published, total), article| {
+                (published + is_public(article, now) as i64, total + 1)
+            })
+    }
+}
+
+/* =================================================================
+
+
+Helpers
+
+
+================================================================== */
+/// `created_at` bounds of the calendar filters of a `QueryArticle`, as its `ToSql` puts them.
+struct CreatedRange {
+    from: Vec<DateTime<Tz>>,
+    before: Vec<DateTime<Tz>>,
+}
+
+impl CreatedRange {
+    fn from_query(req: &QueryArticle) -> Result<Self> {
+        let mut range = Self {
+            from: vec![],
+            before: vec![],
+        };
+        let timezone = parse_timezone(&req.timezone)?;
+        let calendar = calendar_range(req.year, req.month);
+        let (from_date, to_date) = (parse_date(&req.from_date), parse_date(&req.to_date));
+        if let Some((start, end)) = calendar {
+            range.from.push(midnight(start, timezone));
+            range.before.push(midnight(end, timezone));
+        }
+        if let Some(from) = from_date {
+            range.from.push(midnight(from, timezone));
+        }
+        if let Some(to) = to_date {
+            range
+                .before
+                .push(midnight(to + Duration::days(1), timezone));
+        }
+        Ok(range)
+    }
+
+    fn contains(&self, time: DateTime<Local>) -> bool {
+        self.from.iter().all(|from| time >= *from) && self.before.iter().all(|end| time < *end)
+    }
+}
+
+/// The midnight starting `date` in `timezone`, or the first hour after it the day has.
+fn midnight(date: NaiveDate, timezone: Tz) -> DateTime<Tz> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    (0..=24)
+        .find_map(|hours| {
+            timezone
+                .from_local_datetime(&(midnight + Duration::hours(hours)))
+                .earliest()
+        })
+        .unwrap()
+}
+
+/// The current time as Postgres keeps it, to the microsecond.
+fn now() -> Timestamp {
+    let now = Local::now();
+    to_timestamp(now.duration_trunc(Duration::microseconds(1)).unwrap_or(now))
+}
+
+fn created_at(article: &Article) -> DateTime<Local> {
+    article
+        .created_at
+        .as_ref()
+        .map(to_chrono)
+        .unwrap_or_default()
+}
+
+/// `PUBLIC_ARTICLES` for one article.
+fn is_public(article: &Article, now: DateTime<Local>) -> bool {
+    let published = article.publish_at.as_ref().map(to_chrono);
+    let expired = article.expire_at.as_ref().map(to_chrono);
+    article.state == ArticleState::Published as i32
+        && published.is_none_or(|publish_at| publish_at <= now)
+        && expired.is_none_or(|expire_at| expire_at > now)
+}
+
+/// Whether a row is shown under the `Trash` of a query.
+fn in_view(trash: i32, in_trash: bool) -> bool {
+    match Trash::from_i32(trash) {
+        Some(Trash::Include) => true,
+        Some(Trash::Only) => in_trash,
+        _ => !in_trash,
+    }
+}
+
+/// Put a row of `table` in (`to_trash`) or out of the trash bin.
+fn move_to_trash<T>(
+    rows: &mut BTreeMap<ID, Stored<T>>,
+    table: &str,
+    id: ID,
+    to_trash: bool,
+) -> Result<()> {
+    match rows.get_mut(&id) {
+        Some(row) if row.in_trash != to_trash => {
+            row.in_trash = to_trash;
+            Ok(())
+        }
+        _ => {
+            let place = if to_trash { "out of" } else { "in" };
+            Err(BackendError::InvalidRequest(format!(
+                "No row {} of {} {} the trash bin.",
+                id, table, place
+            )))
+        }
+    }
+}
+
+fn ensure_in_trash<T>(rows: &BTreeMap<ID, Stored<T>>, table: &str, id: ID) -> Result<()> {
+    match rows.get(&id) {
+        Some(row) if row.in_trash => Ok(()),
+        _ => Err(BackendError::InvalidRequest(format!(
+            "No row {} of {} in the trash bin.",
+            id, table
+        ))),
+    }
+}
+
+/// What Postgres fails with on a row that is not there.
+fn not_found() -> BackendError {
+    BackendError::SqlxError(sqlx::Error::RowNotFound)
+}
+
+/// The unique index on the names of the rows out of the trash bin.
+fn name_taken(table: &str, name: &str) -> BackendError {
+    BackendError::InvalidRequest(format!("{} has a row named {} already.", table, name))
+}
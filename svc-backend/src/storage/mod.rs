@@ -4,14 +4,18 @@ use std::ops::Deref;
 
 use sqlx::PgPool;
 
+pub use memory::MemoryDB;
 pub use traits::{BlogDB, ID};
 
 mod implements;
+mod memory;
 mod traits;
+mod writes;
 
 #[cfg(test)]
 mod tests;
 
+#[derive(Clone)]
 pub struct DBPool {
     pool: PgPool,
 }
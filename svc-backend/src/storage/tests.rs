@@ -1,12 +1,19 @@
+use std::ops::Deref;
 use std::path::Path;
 
 use sqlx::PgPool;
 use sqlx_db_tester::TestPg;
 
-use util_pb::{to_timestamp, QueryArticle};
+use util_pb::transfer::Cursor;
+use util_pb::{
+    to_timestamp, ArchiveRequest, ArticleProjection, ArticleState, ArticleStateFilter, FieldMask,
+    QueryArticle, QueryCategory, QueryTag, SearchRequest, SortDirection, SortKey, TagMatch, Trash,
+    Visibility,
+};
 
 use crate::storage::traits::BlogDB;
-use crate::storage::DBPool;
+use crate::storage::{DBPool, MemoryDB};
+use crate::BackendError;
 
 async fn load_test_db() -> TestPg {
     dotenv::dotenv().ok();
@@ -28,9 +35,16 @@ async fn load_pool() -> PgPool {
 #[tokio::test]
 async fn query_article_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    query_article(db).await;
+}
+
+#[tokio::test]
+async fn query_article_should_work_in_memory() {
+    query_article(MemoryDB::sample()).await;
+}
 
+async fn query_article<DB: BlogDB>(db: DB) {
     // take all
     let req = QueryArticle::default();
     let res = db.query_articles(req).await;
@@ -53,9 +67,8 @@ async fn query_article_should_work() {
     assert_eq!(res[0].id, 1000);
 
     // by time
-    let this_year = chrono::Local::now();
     let req = QueryArticle {
-        created_year: Some(to_timestamp(this_year)),
+        year: chrono::Datelike::year(&chrono::Utc::now()),
         ..QueryArticle::default()
     };
     let res = db.query_articles(req).await;
@@ -73,10 +86,16 @@ async fn query_article_should_work() {
 #[tokio::test]
 async fn add_article_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    // let pool = load_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    add_article(db).await;
+}
+
+#[tokio::test]
+async fn add_article_should_work_in_memory() {
+    add_article(MemoryDB::sample()).await;
+}
 
+async fn add_article<DB: BlogDB>(db: DB) {
     // add one
     let req = util_pb::Article {
         title: "test_add".to_string(),
@@ -94,16 +113,23 @@ async fn add_article_should_work() {
     };
     let res = db.query_articles(req).await.unwrap();
     assert_eq!(res[0].id, id);
-    assert_eq!(res[0].tags_id, vec![1, 2, 0]);
+    assert_eq!(res[0].tags_id, vec![1, 2]);
     assert_eq!(res[0].summary, "test add".to_string());
 }
 
 #[tokio::test]
 async fn update_article_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    update_article(db).await;
+}
 
+#[tokio::test]
+async fn update_article_should_work_in_memory() {
+    update_article(MemoryDB::sample()).await;
+}
+
+async fn update_article<DB: BlogDB>(db: DB) {
     let req = util_pb::Article {
         id: 1000,
         title: "test_update".to_string(),
@@ -112,7 +138,7 @@ async fn update_article_should_work() {
         category_id: 1,
         ..util_pb::Article::default()
     };
-    db.edit_article(req).await.unwrap();
+    db.edit_article(req, None).await.unwrap();
 
     let req = QueryArticle {
         title: "test_update".to_string(),
@@ -127,7 +153,7 @@ async fn update_article_should_work() {
         category_id: 1,
         ..util_pb::Article::default()
     };
-    db.edit_article(req).await.unwrap();
+    db.edit_article(req, None).await.unwrap();
 
     let req = QueryArticle {
         title: "test_update".to_string(),
@@ -141,9 +167,16 @@ async fn update_article_should_work() {
 #[tokio::test]
 async fn delete_article_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    delete_article(db).await;
+}
+
+#[tokio::test]
+async fn delete_article_should_work_in_memory() {
+    delete_article(MemoryDB::sample()).await;
+}
 
+async fn delete_article<DB: BlogDB>(db: DB) {
     let req = util_pb::Article {
         title: "test_delete".to_string(),
         content: "test delete".to_string(),
@@ -160,9 +193,16 @@ async fn delete_article_should_work() {
 #[tokio::test]
 async fn categories_operator_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    categories_operator(db).await;
+}
 
+#[tokio::test]
+async fn categories_operator_should_work_in_memory() {
+    categories_operator(MemoryDB::sample()).await;
+}
+
+async fn categories_operator<DB: BlogDB>(db: DB) {
     // query
     let req = util_pb::QueryCategory::default();
     let res = db.query_categories(req).await.unwrap();
@@ -186,8 +226,9 @@ async fn categories_operator_should_work() {
     let req = util_pb::Category {
         id: new_id,
         name: "test_edit".to_string(),
+        ..util_pb::Category::default()
     };
-    db.edit_category(req).await.unwrap();
+    db.edit_category(req, None).await.unwrap();
 
     let req = util_pb::QueryCategory {
         ids: vec![new_id],
@@ -196,8 +237,23 @@ async fn categories_operator_should_work() {
     let res = db.query_categories(req).await.unwrap();
     assert_eq!(res[0].name, "test_edit".to_string());
 
+    // not after another category
+    let name = db
+        .query_categories(util_pb::QueryCategory::default())
+        .await
+        .unwrap()[1]
+        .name
+        .clone();
+    let req = util_pb::Category {
+        id: new_id,
+        name,
+        ..util_pb::Category::default()
+    };
+    let err = db.edit_category(req, None).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+
     // delete
-    db.delete_category(new_id).await.unwrap();
+    db.delete_category(new_id, None).await.unwrap();
 
     let req = util_pb::QueryCategory::default();
     let res = db.query_categories(req).await.unwrap();
@@ -207,9 +263,16 @@ async fn categories_operator_should_work() {
 #[tokio::test]
 async fn tags_operator_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    tags_operator(db).await;
+}
 
+#[tokio::test]
+async fn tags_operator_should_work_in_memory() {
+    tags_operator(MemoryDB::sample()).await;
+}
+
+async fn tags_operator<DB: BlogDB>(db: DB) {
     // query
     let req = util_pb::QueryTag::default();
     let res = db.query_tags(req).await.unwrap();
@@ -233,8 +296,9 @@ async fn tags_operator_should_work() {
     let req = util_pb::Tag {
         id: new_id,
         name: "test_edit".to_string(),
+        ..util_pb::Tag::default()
     };
-    db.edit_tag(req).await.unwrap();
+    db.edit_tag(req, None).await.unwrap();
 
     let req = util_pb::QueryTag {
         ids: vec![new_id],
@@ -243,6 +307,15 @@ async fn tags_operator_should_work() {
     let res = db.query_tags(req).await.unwrap();
     assert_eq!(res[0].name, "test_edit".to_string());
 
+    // not after another tag
+    let req = util_pb::Tag {
+        id: new_id,
+        name: "Test_tag1".to_string(),
+        ..util_pb::Tag::default()
+    };
+    let err = db.edit_tag(req, None).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+
     // delete
     db.delete_tag(new_id).await.unwrap();
 
@@ -254,9 +327,16 @@ async fn tags_operator_should_work() {
 #[tokio::test]
 async fn tag_article_should_work() {
     let tdb = load_test_db().await;
-    let pool = tdb.get_pool().await;
-    let db = DBPool::new(pool);
+    let db = DBPool::new(tdb.get_pool().await);
+    tag_article(db).await;
+}
 
+#[tokio::test]
+async fn tag_article_should_work_in_memory() {
+    tag_article(MemoryDB::sample()).await;
+}
+
+async fn tag_article<DB: BlogDB>(db: DB) {
     // delete article first
     let req = util_pb::Tag {
         name: "test_tag".to_string(),
@@ -298,6 +378,1536 @@ async fn tag_article_should_work() {
         ids: vec![article_id],
         ..util_pb::QueryArticle::default()
     };
-    let res = db.query_articles(req).await.unwrap();
+    let res = db.query_articles(req.clone()).await.unwrap();
     assert_eq!(res[0].tags_id, vec![0]);
+
+    // the restored tag takes over from tag 0
+    db.restore_tag(tag_id).await.unwrap();
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].tags_id, vec![tag_id]);
+}
+
+#[tokio::test]
+async fn trash_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    db.rebuild_search_index().await.unwrap();
+    trash(db).await;
+}
+
+#[tokio::test]
+async fn trash_should_work_in_memory() {
+    trash(MemoryDB::sample()).await;
+}
+
+async fn trash<DB: BlogDB>(db: DB) {
+    // articles
+    db.delete_article(1000).await.unwrap();
+    assert!(db.delete_article(1000).await.is_err());
+
+    let res = db.query_articles(QueryArticle::default()).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1001]);
+    assert_eq!(db.count_articles(QueryArticle::default()).await.unwrap(), 1);
+    let req = QueryArticle {
+        trash: Trash::Only as i32,
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1000]);
+    let req = QueryArticle {
+        trash: Trash::Include as i32,
+        ..QueryArticle::default()
+    };
+    assert_eq!(db.count_articles(req).await.unwrap(), 2);
+    let req = SearchRequest {
+        query: "test_title".to_string(),
+        ..SearchRequest::default()
+    };
+    assert_eq!(db.search(req).await.unwrap().total, 1);
+
+    db.restore_article(1000).await.unwrap();
+    assert!(db.restore_article(1000).await.is_err());
+    assert_eq!(db.count_articles(QueryArticle::default()).await.unwrap(), 2);
+
+    // only what is in the trash bin can be purged
+    assert!(db.purge_article(1001).await.is_err());
+    db.delete_article(1001).await.unwrap();
+    db.purge_article(1001).await.unwrap();
+    let req = QueryArticle {
+        trash: Trash::Include as i32,
+        ..QueryArticle::default()
+    };
+    assert_eq!(db.count_articles(req).await.unwrap(), 1);
+
+    // tags in the trash bin leave their articles, and come back with their links
+    db.delete_tag(1).await.unwrap();
+    let req = util_pb::QueryTag {
+        trash: Trash::Only as i32,
+        ..util_pb::QueryTag::default()
+    };
+    assert_eq!(db.query_tags(req).await.unwrap()[0].id, 1);
+    let req = QueryArticle {
+        ids: vec![1000],
+        ..QueryArticle::default()
+    };
+    assert_eq!(
+        db.query_articles(req.clone()).await.unwrap()[0].tags_id,
+        vec![2]
+    );
+
+    let edit = util_pb::Article {
+        id: 1000,
+        title: "edited".to_string(),
+        tags_id: vec![2],
+        ..util_pb::Article::default()
+    };
+    db.edit_article(edit, None).await.unwrap();
+    db.restore_tag(1).await.unwrap();
+    let mut tags_id = db.query_articles(req.clone()).await.unwrap()[0]
+        .tags_id
+        .clone();
+    tags_id.sort();
+    assert_eq!(tags_id, vec![1, 2]);
+
+    // categories
+    db.delete_category(1, None).await.unwrap();
+    assert_eq!(
+        db.query_categories(util_pb::QueryCategory::default())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    db.restore_category(1).await.unwrap();
+    db.delete_category(1, None).await.unwrap();
+    // its articles went to category 0
+    db.purge_category(1).await.unwrap();
+    assert_eq!(db.query_articles(req).await.unwrap()[0].category_id, 0);
+}
+
+#[tokio::test]
+async fn restore_should_keep_names_unique() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    restore_names(db).await;
+}
+
+#[tokio::test]
+async fn restore_should_keep_names_unique_in_memory() {
+    restore_names(MemoryDB::sample()).await;
+}
+
+async fn restore_names<DB: BlogDB>(db: DB) {
+    // the name of a row in the trash bin can be used again, and then the row stays there
+    let category = util_pb::Category {
+        name: "reused".to_string(),
+        ..util_pb::Category::default()
+    };
+    let old_id = db.add_category(category.clone()).await.unwrap();
+    db.delete_category(old_id, None).await.unwrap();
+    let new_id = db.add_category(category).await.unwrap();
+    let err = db.restore_category(old_id).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    db.delete_category(new_id, None).await.unwrap();
+    db.restore_category(old_id).await.unwrap();
+
+    let tag = util_pb::Tag {
+        name: "reused".to_string(),
+        ..util_pb::Tag::default()
+    };
+    let old_id = db.add_tag(tag.clone()).await.unwrap();
+    db.delete_tag(old_id).await.unwrap();
+    let new_id = db.add_tag(tag).await.unwrap();
+    let err = db.restore_tag(old_id).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    db.delete_tag(new_id).await.unwrap();
+    db.restore_tag(old_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn default_category_and_tag_should_stay() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    default_category_and_tag(db).await;
+}
+
+#[tokio::test]
+async fn default_category_and_tag_should_stay_in_memory() {
+    default_category_and_tag(MemoryDB::sample()).await;
+}
+
+async fn default_category_and_tag<DB: BlogDB>(db: DB) {
+    let get = |id| QueryArticle {
+        ids: vec![id],
+        ..QueryArticle::default()
+    };
+    let invalid = |res: crate::Result<_>| matches!(res, Err(BackendError::InvalidRequest(_)));
+
+    assert!(invalid(db.delete_category(0, None).await));
+    assert!(invalid(db.delete_tag(0).await));
+    let category = util_pb::Category {
+        id: 0,
+        name: "renamed".to_string(),
+        ..util_pb::Category::default()
+    };
+    assert!(invalid(db.edit_category(category, None).await.map(drop)));
+    let tag = util_pb::Tag {
+        id: 0,
+        name: "renamed".to_string(),
+        ..util_pb::Tag::default()
+    };
+    assert!(invalid(db.edit_tag(tag, None).await.map(drop)));
+
+    // articles losing their last tag get tag 0
+    let mask = Some(FieldMask {
+        paths: vec!["tags_id".to_string()],
+    });
+    let untag = util_pb::Article {
+        id: 1001,
+        ..util_pb::Article::default()
+    };
+    db.edit_article(untag, mask).await.unwrap();
+    assert_eq!(
+        db.query_articles(get(1001)).await.unwrap()[0].tags_id,
+        vec![0]
+    );
+    db.delete_tag(1).await.unwrap();
+    db.purge_tag(1).await.unwrap();
+    assert_eq!(db.article_to_tags(1000).await.unwrap(), vec![2]);
+
+    // a category moves its articles where asked, but not to itself or to the trash bin
+    let category = util_pb::Category {
+        name: "elsewhere".to_string(),
+        ..util_pb::Category::default()
+    };
+    let elsewhere = db.add_category(category).await.unwrap();
+    assert!(invalid(db.delete_category(1, Some(1)).await));
+    db.delete_category(elsewhere, None).await.unwrap();
+    assert!(invalid(db.delete_category(1, Some(elsewhere)).await));
+    db.restore_category(elsewhere).await.unwrap();
+    db.delete_category(1, Some(elsewhere)).await.unwrap();
+    assert_eq!(
+        db.query_articles(get(1000)).await.unwrap()[0].category_id,
+        elsewhere
+    );
+}
+
+#[tokio::test]
+async fn public_visibility_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    public_visibility(db).await;
+}
+
+#[tokio::test]
+async fn public_visibility_should_work_in_memory() {
+    public_visibility(MemoryDB::sample()).await;
+}
+
+async fn public_visibility<DB: BlogDB>(db: DB) {
+    let now = chrono::Local::now();
+    let scheduled = util_pb::Article {
+        title: "scheduled".to_string(),
+        content: "visibility".to_string(),
+        state: ArticleState::Scheduled as i32,
+        category_id: 1,
+        publish_at: Some(to_timestamp(now + chrono::Duration::days(1))),
+        ..util_pb::Article::default()
+    };
+    let scheduled = db.add_article(scheduled).await.unwrap();
+    // published by hand before its publish_at
+    let early = util_pb::Article {
+        title: "early".to_string(),
+        content: "visibility".to_string(),
+        state: ArticleState::Published as i32,
+        category_id: 1,
+        publish_at: Some(to_timestamp(now + chrono::Duration::days(1))),
+        ..util_pb::Article::default()
+    };
+    let early = db.add_article(early).await.unwrap();
+    // expired, but the publisher has not run yet
+    let expired = util_pb::Article {
+        title: "expired".to_string(),
+        content: "visibility".to_string(),
+        state: ArticleState::Published as i32,
+        category_id: 1,
+        expire_at: Some(to_timestamp(now - chrono::Duration::days(1))),
+        ..util_pb::Article::default()
+    };
+    let expired = db.add_article(expired).await.unwrap();
+    let hidden = util_pb::Article {
+        id: 1001,
+        state: ArticleState::Hidden as i32,
+        ..util_pb::Article::default()
+    };
+    db.edit_article(hidden, None).await.unwrap();
+
+    let public = QueryArticle {
+        visibility: Visibility::Public as i32,
+        sort_key: SortKey::Id as i32,
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(public.clone()).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1000]);
+    assert_eq!(db.count_articles(public.clone()).await.unwrap(), 1);
+
+    // by id too
+    for id in [scheduled, early, expired, 1001] {
+        let req = QueryArticle {
+            ids: vec![id],
+            ..public.clone()
+        };
+        assert!(db.query_articles(req).await.unwrap().is_empty());
+    }
+
+    // management still sees everything
+    assert_eq!(db.count_articles(QueryArticle::default()).await.unwrap(), 5);
+
+    let req = SearchRequest {
+        query: "visibility".to_string(),
+        visibility: Visibility::Public as i32,
+        ..SearchRequest::default()
+    };
+    assert_eq!(db.search(req.clone()).await.unwrap().total, 0);
+    let req = SearchRequest {
+        visibility: Visibility::All as i32,
+        ..req
+    };
+    assert_eq!(db.search(req).await.unwrap().total, 3);
+}
+
+#[tokio::test]
+async fn field_masks_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    field_masks(db).await;
+}
+
+#[tokio::test]
+async fn field_masks_should_work_in_memory() {
+    field_masks(MemoryDB::sample()).await;
+}
+
+async fn field_masks<DB: BlogDB>(db: DB) {
+    let mask = |paths: &[&str]| {
+        Some(FieldMask {
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+        })
+    };
+    let get = |id| QueryArticle {
+        ids: vec![id],
+        ..QueryArticle::default()
+    };
+
+    let req = util_pb::Article {
+        title: "masked".to_string(),
+        content: "masked content".to_string(),
+        summary: "masked summary".to_string(),
+        state: ArticleState::Published as i32,
+        category_id: 1,
+        tags_id: vec![1, 2],
+        expire_at: Some(to_timestamp(
+            chrono::Local::now() + chrono::Duration::days(1),
+        )),
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+
+    // the listed fields are cleared, the others left alone though given
+    let req = util_pb::Article {
+        id,
+        title: "not listed".to_string(),
+        ..util_pb::Article::default()
+    };
+    let paths = ["summary", "category_id", "tags_id", "expire_at"];
+    db.edit_article(req, mask(&paths)).await.unwrap();
+    let article = db.query_articles(get(id)).await.unwrap().remove(0);
+    assert_eq!(article.title, "masked");
+    assert_eq!(article.content, "masked content");
+    assert_eq!(article.summary, "");
+    assert_eq!(article.category_id, 0);
+    // cleared down to the default tag
+    assert_eq!(article.tags_id, vec![0]);
+    assert_eq!(article.expire_at, None);
+
+    // only the listed tags change
+    let req = util_pb::Article {
+        id,
+        tags_id: vec![2],
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req.clone(), mask(&["title"]))
+        .await
+        .unwrap();
+    let article = db.query_articles(get(id)).await.unwrap().remove(0);
+    assert_eq!(article.tags_id, vec![0]);
+    db.edit_article(req, mask(&["tags_id"])).await.unwrap();
+    let article = db.query_articles(get(id)).await.unwrap().remove(0);
+    assert_eq!(article.tags_id, vec![2]);
+
+    let req = util_pb::Article {
+        id,
+        ..util_pb::Article::default()
+    };
+    let err = db.edit_article(req.clone(), mask(&["id"])).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    // a state must be given when listed
+    let err = db.edit_article(req, mask(&["state"])).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+
+    // an empty mask leaves the name alone
+    let category = util_pb::Category {
+        id: 1,
+        name: "".to_string(),
+        ..util_pb::Category::default()
+    };
+    db.edit_category(category.clone(), mask(&[])).await.unwrap();
+    let categories = db.query_categories(QueryCategory::default()).await.unwrap();
+    assert!(categories.iter().any(|c| c.name == "Test_category1"));
+    let err = db.edit_category(category, mask(&["id"])).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn stale_edits_should_conflict() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    stale_edits(db).await;
+}
+
+#[tokio::test]
+async fn stale_edits_should_conflict_in_memory() {
+    stale_edits(MemoryDB::sample()).await;
+}
+
+async fn stale_edits<DB: BlogDB>(db: DB) {
+    let get = || QueryArticle {
+        ids: vec![1000],
+        ..QueryArticle::default()
+    };
+    let loaded = db.query_articles(get()).await.unwrap().remove(0);
+    assert_eq!(loaded.version, 1);
+
+    // two editors load version 1, the first one saves
+    let first = util_pb::Article {
+        title: "first".to_string(),
+        ..loaded.clone()
+    };
+    db.edit_article(first, None).await.unwrap();
+    let second = util_pb::Article {
+        title: "second".to_string(),
+        ..loaded
+    };
+    let err = db.edit_article(second.clone(), None).await;
+    assert!(matches!(err, Err(BackendError::Conflict(_))));
+
+    let article = db.query_articles(get()).await.unwrap().remove(0);
+    assert_eq!(article.title, "first");
+    assert_eq!(article.version, 2);
+
+    // on the current version, or without one, the edit goes through
+    db.edit_article(
+        util_pb::Article {
+            version: 2,
+            ..second.clone()
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    db.edit_article(
+        util_pb::Article {
+            version: 0,
+            ..second
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    let article = db.query_articles(get()).await.unwrap().remove(0);
+    assert_eq!(article.title, "second");
+    assert_eq!(article.version, 4);
+}
+
+#[tokio::test]
+async fn category_tree_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    category_tree(db).await;
+}
+
+#[tokio::test]
+async fn category_tree_should_work_in_memory() {
+    category_tree(MemoryDB::sample()).await;
+}
+
+async fn category_tree<DB: BlogDB>(db: DB) {
+    let add = |name: &str, parent_id: Option<i32>| util_pb::Category {
+        name: name.to_string(),
+        parent_id,
+        ..util_pb::Category::default()
+    };
+    let programming = db.add_category(add("Programming", None)).await.unwrap();
+    let rust = db
+        .add_category(add("Rust", Some(programming)))
+        .await
+        .unwrap();
+    let async_ = db.add_category(add("Async", Some(rust))).await.unwrap();
+
+    let req = util_pb::Article {
+        title: "tree".to_string(),
+        content: "tree".to_string(),
+        state: ArticleState::Published as i32,
+        category_id: async_,
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+
+    let in_programming = |include_descendants| QueryArticle {
+        category_id: programming,
+        include_descendants,
+        ..QueryArticle::default()
+    };
+    assert!(db
+        .query_articles(in_programming(false))
+        .await
+        .unwrap()
+        .is_empty());
+    let res = db.query_articles(in_programming(true)).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![id]);
+
+    // no cycles
+    let mask = Some(FieldMask {
+        paths: vec!["parent_id".to_string()],
+    });
+    for parent_id in [programming, async_] {
+        let req = util_pb::Category {
+            id: programming,
+            parent_id: Some(parent_id),
+            ..util_pb::Category::default()
+        };
+        let err = db.edit_category(req, mask.clone()).await;
+        assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    }
+
+    // Rust becomes a root, its subtree moving along
+    let req = util_pb::Category {
+        id: rust,
+        parent_id: None,
+        ..util_pb::Category::default()
+    };
+    db.edit_category(req, mask).await.unwrap();
+    let req = QueryCategory {
+        ids: vec![rust],
+        ..QueryCategory::default()
+    };
+    let res = db.query_categories(req).await.unwrap();
+    assert_eq!(res[0].name, "Rust");
+    assert_eq!(res[0].parent_id, None);
+    assert!(db
+        .query_articles(in_programming(true))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn merge_tags_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    merge_tags(db).await;
+}
+
+#[tokio::test]
+async fn merge_tags_should_work_in_memory() {
+    merge_tags(MemoryDB::sample()).await;
+}
+
+async fn merge_tags<DB: BlogDB>(db: DB) {
+    let mut ids = vec![];
+    for name in ["rust", "rust-upper", "rust-lang"] {
+        let tag = util_pb::Tag {
+            name: name.to_string(),
+            ..util_pb::Tag::default()
+        };
+        ids.push(db.add_tag(tag).await.unwrap());
+    }
+    let (rust, upper, lang) = (ids[0], ids[1], ids[2]);
+    // adding `Rust` would find `rust`, so it is a rename
+    let tag = util_pb::Tag {
+        id: upper,
+        name: "Rust".to_string(),
+        ..util_pb::Tag::default()
+    };
+    db.edit_tag(tag, None).await.unwrap();
+
+    let tag = |id, tags_id| util_pb::Article {
+        id,
+        tags_id,
+        ..util_pb::Article::default()
+    };
+    db.edit_article(tag(1000, vec![1, 2, lang]), None)
+        .await
+        .unwrap();
+    db.edit_article(tag(1001, vec![2, rust, upper]), None)
+        .await
+        .unwrap();
+    // a revision holding both sources
+    db.edit_article(tag(1001, vec![2, rust, upper]), None)
+        .await
+        .unwrap();
+
+    let articles = db.merge_tags(vec![upper, lang], rust).await.unwrap();
+    assert_eq!(articles, 2);
+    assert_eq!(db.article_to_tags(1000).await.unwrap(), vec![1, 2, rust]);
+    let mut tags = db.article_to_tags(1001).await.unwrap();
+    tags.sort();
+    assert_eq!(tags, vec![2, rust]);
+    // the names of the sources cannot go to another tag
+    let rename = util_pb::Tag {
+        id: 2,
+        name: "RUST-LANG".to_string(),
+        ..util_pb::Tag::default()
+    };
+    let err = db.edit_tag(rename, None).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    let req = QueryTag {
+        ids: vec![upper, lang],
+        trash: Trash::Include as i32,
+        ..QueryTag::default()
+    };
+    assert!(db.query_tags(req).await.unwrap().is_empty());
+    for revision in db.list_revisions(1001).await.unwrap() {
+        assert!(!revision.tags_id.contains(&upper));
+    }
+
+    // the merged names resolve to the target, and follow it into another merge
+    let tag = |name: &str| util_pb::Tag {
+        name: name.to_string(),
+        ..util_pb::Tag::default()
+    };
+    assert_eq!(db.add_tag(tag("Rust")).await.unwrap(), rust);
+    db.merge_tags(vec![rust], 1).await.unwrap();
+    assert_eq!(db.add_tag(tag("rust-lang")).await.unwrap(), 1);
+    assert_eq!(db.add_tag(tag("rust")).await.unwrap(), 1);
+
+    for (source_ids, target_id) in [(vec![1], 1), (vec![0], 1), (vec![2], 99), (vec![], 1)] {
+        let err = db.merge_tags(source_ids, target_id).await;
+        assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    }
+}
+
+#[tokio::test]
+async fn resolve_tags_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    resolve_tags(db).await;
+}
+
+#[tokio::test]
+async fn resolve_tags_should_work_in_memory() {
+    resolve_tags(MemoryDB::sample()).await;
+}
+
+async fn resolve_tags<DB: BlogDB>(db: DB) {
+    let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+    // `Test_tag` is a prefix of both fixtures and matches neither
+    let ids = db
+        .resolve_tags(names(&[
+            "test_tag1",
+            " Test_tag2 ",
+            "",
+            "Test_tag",
+            "TEST_TAG1",
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[..2], [1, 2]);
+    let created = ids[2];
+    let req = QueryTag {
+        ids: vec![created],
+        ..QueryTag::default()
+    };
+    assert_eq!(db.query_tags(req).await.unwrap()[0].name, "Test_tag");
+
+    // once created, found again whatever the case
+    let ids = db.resolve_tags(names(&["test_TAG"])).await.unwrap();
+    assert_eq!(ids, vec![created]);
+
+    // aliases of merged tags
+    let merged = db.resolve_tags(names(&["rust-lang"])).await.unwrap()[0];
+    db.merge_tags(vec![merged], 1).await.unwrap();
+    let ids = db
+        .resolve_tags(names(&["Rust-Lang", "Test_tag1"]))
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1]);
+    assert!(db.resolve_tags(vec![]).await.unwrap().is_empty());
+
+    // adding a tag looks it up the same way
+    let tag = |name: &str| util_pb::Tag {
+        name: name.to_string(),
+        ..util_pb::Tag::default()
+    };
+    assert_eq!(db.add_tag(tag(" TEST_TAG2 ")).await.unwrap(), 2);
+    assert_eq!(db.add_tag(tag("RUST-LANG")).await.unwrap(), 1);
+    let err = db.add_tag(tag(" ")).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn find_tags_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    find_tags(db).await;
+}
+
+#[tokio::test]
+async fn find_tags_should_work_in_memory() {
+    find_tags(MemoryDB::sample()).await;
+}
+
+async fn find_tags<DB: BlogDB>(db: DB) {
+    let names = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    let merged = db.resolve_tags(names(&["rust-lang"])).await.unwrap()[0];
+    db.merge_tags(vec![merged], 1).await.unwrap();
+    let tags = db.query_tags(QueryTag::default()).await.unwrap();
+
+    // matched as `resolve_tags` does, aliases included, but nothing is created
+    let (ids, unknown) = db
+        .find_tags(names(&[
+            "TEST_TAG2",
+            " Rust-Lang ",
+            "",
+            "Test_tag",
+            "test_tag1",
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![2, 1]);
+    assert_eq!(unknown, names(&["Test_tag"]));
+    assert_eq!(db.query_tags(QueryTag::default()).await.unwrap(), tags);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_resolve_tags_should_agree() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    concurrent_resolve_tags(db).await;
+}
+
+#[tokio::test]
+async fn concurrent_resolve_tags_should_agree_in_memory() {
+    concurrent_resolve_tags(MemoryDB::sample()).await;
+}
+
+async fn concurrent_resolve_tags<DB: BlogDB + Clone + 'static>(db: DB) {
+    // requests creating the same tags at once all get them
+    let names = (0..10).map(|i| format!("racing{}", i)).collect::<Vec<_>>();
+    let tasks = (0..8)
+        .map(|_| {
+            let (db, names) = (db.clone(), names.clone());
+            tokio::spawn(async move { db.resolve_tags(names).await })
+        })
+        .collect::<Vec<_>>();
+    let mut ids = vec![];
+    for task in tasks {
+        ids.push(task.await.unwrap().unwrap());
+    }
+    ids.dedup();
+    assert_eq!(ids.len(), 1);
+}
+
+#[tokio::test]
+async fn state_transitions_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    state_transitions(db).await;
+}
+
+#[tokio::test]
+async fn state_transitions_should_work_in_memory() {
+    state_transitions(MemoryDB::sample()).await;
+}
+
+async fn state_transitions<DB: BlogDB>(db: DB) {
+    // created as a draft when no state is given
+    let req = util_pb::Article {
+        title: "draft".to_string(),
+        content: "draft".to_string(),
+        category_id: 1,
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+
+    let move_to = |state: ArticleState| util_pb::Article {
+        id,
+        state: state as i32,
+        ..util_pb::Article::default()
+    };
+    let err = db.edit_article(move_to(ArticleState::Archived), None).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    db.edit_article(move_to(ArticleState::Published), None)
+        .await
+        .unwrap();
+    let err = db.edit_article(move_to(ArticleState::Draft), None).await;
+    assert!(matches!(err, Err(BackendError::InvalidRequest(_))));
+    db.edit_article(move_to(ArticleState::Archived), None)
+        .await
+        .unwrap();
+    // no change of state
+    db.edit_article(move_to(ArticleState::Unspecified), None)
+        .await
+        .unwrap();
+
+    // filters
+    let by_states = |states: Vec<ArticleState>| QueryArticle {
+        state_filter: Some(ArticleStateFilter {
+            states: states.into_iter().map(|state| state as i32).collect(),
+        }),
+        sort_key: SortKey::Id as i32,
+        ..QueryArticle::default()
+    };
+    let ids = |articles: Vec<util_pb::Article>| articles.iter().map(|a| a.id).collect::<Vec<_>>();
+    let res = db
+        .query_articles(by_states(vec![ArticleState::Archived]))
+        .await
+        .unwrap();
+    assert_eq!(ids(res), vec![id]);
+    let res = db
+        .query_articles(by_states(vec![
+            ArticleState::Archived,
+            ArticleState::Published,
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(ids(res), vec![1001, 1000, id]);
+    let res = db.query_articles(by_states(vec![])).await.unwrap();
+    assert_eq!(res.len(), 3);
+}
+
+#[tokio::test]
+async fn special_characters_should_round_trip() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    special_characters(db).await;
+}
+
+#[tokio::test]
+async fn special_characters_should_round_trip_in_memory() {
+    special_characters(MemoryDB::sample()).await;
+}
+
+async fn special_characters<DB: BlogDB>(db: DB) {
+    let title = r#"it's 100% "quoted" \ back_slash"#.to_string();
+    let content = r#"'; DROP TABLE blog.articles; -- \n 50% off_the _shelf"#.to_string();
+    let req = util_pb::Article {
+        title: title.clone(),
+        content: content.clone(),
+        category_id: 1,
+        tags_id: vec![1],
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+
+    // the wildcards in the keyword are matched literally
+    let req = QueryArticle {
+        title: r#"100% "quoted" \ back_"#.to_string(),
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].id, id);
+    assert_eq!(res[0].title, title);
+    assert_eq!(res[0].content, content);
+
+    let req = QueryArticle {
+        title: "e_t".to_string(),
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert!(res.is_empty());
+
+    // update with quotes
+    let new_content = r#"O'Reilly \' \\ %_"#.to_string();
+    let req = util_pb::Article {
+        id,
+        content: new_content.clone(),
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+
+    let req = QueryArticle {
+        ids: vec![id],
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, title);
+    assert_eq!(res[0].content, new_content);
+    assert_eq!(res[0].summary, new_content);
+
+    // tags and categories
+    let name = r#"C'est_100%"#.to_string();
+    let tag_id = db
+        .add_tag(util_pb::Tag {
+            name: name.clone(),
+            ..util_pb::Tag::default()
+        })
+        .await
+        .unwrap();
+    let req = util_pb::QueryTag {
+        name: "e_t".to_string(),
+        ..util_pb::QueryTag::default()
+    };
+    assert!(db.query_tags(req).await.unwrap().is_empty());
+    let req = util_pb::QueryTag {
+        name: "'est_100%".to_string(),
+        ..util_pb::QueryTag::default()
+    };
+    let res = db.query_tags(req).await.unwrap();
+    assert_eq!(res[0].id, tag_id);
+
+    let category_id = db
+        .add_category(util_pb::Category {
+            name: name.clone(),
+            ..util_pb::Category::default()
+        })
+        .await
+        .unwrap();
+    let req = util_pb::QueryCategory {
+        name: name.clone(),
+        ..util_pb::QueryCategory::default()
+    };
+    let res = db.query_categories(req).await.unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].id, category_id);
+}
+
+#[tokio::test]
+async fn failed_article_writes_should_roll_back() {
+    let tdb = load_test_db().await;
+    let pool = tdb.get_pool().await;
+    let db = DBPool::new(pool);
+
+    // add: the second tag does not exist
+    let req = util_pb::Article {
+        title: "test_rollback".to_string(),
+        content: "test rollback".to_string(),
+        category_id: 1,
+        tags_id: vec![1, 9999],
+        ..util_pb::Article::default()
+    };
+    assert!(db.add_article(req).await.is_err());
+
+    let req = QueryArticle {
+        title: "test_rollback".to_string(),
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert!(res.is_empty());
+
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM blog.articles WHERE title = $1")
+        .bind("test_rollback")
+        .fetch_one(db.deref())
+        .await
+        .unwrap();
+    assert_eq!(row.0, 0);
+
+    // edit: neither the new title nor the removed tag should stick
+    let req = util_pb::Article {
+        id: 1000,
+        title: "test_rollback".to_string(),
+        tags_id: vec![1, 9999],
+        ..util_pb::Article::default()
+    };
+    assert!(db.edit_article(req, None).await.is_err());
+
+    let req = QueryArticle {
+        ids: vec![1000],
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, "test_title".to_string());
+    assert_eq!(res[0].tags_id, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn query_article_pagination_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    query_article_pagination(db).await;
+}
+
+#[tokio::test]
+async fn query_article_pagination_should_work_in_memory() {
+    query_article_pagination(MemoryDB::sample()).await;
+}
+
+async fn query_article_pagination<DB: BlogDB>(db: DB) {
+    // lowercase ASCII titles, which sort the same in memory and under any collation
+    for title in ["b_page", "a_page", "c_page"] {
+        let req = util_pb::Article {
+            title: title.to_string(),
+            content: "test page".to_string(),
+            category_id: 1,
+            ..util_pb::Article::default()
+        };
+        db.add_article(req).await.unwrap();
+    }
+
+    let req = QueryArticle {
+        title: "_page".to_string(),
+        page_size: 2,
+        sort_key: SortKey::Title as i32,
+        sort_direction: SortDirection::Asc as i32,
+        ..QueryArticle::default()
+    };
+    assert_eq!(db.count_articles(req.clone()).await.unwrap(), 3);
+
+    let res = db.query_articles(req.clone()).await.unwrap();
+    let titles = res.iter().map(|a| a.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(titles, vec!["a_page", "b_page"]);
+
+    let cursor = Cursor::from_article(&res[1], SortKey::Title).encode();
+    let req = QueryArticle { cursor, ..req };
+    let res = db.query_articles(req).await.unwrap();
+    let titles = res.iter().map(|a| a.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(titles, vec!["c_page"]);
+
+    // newest first by default
+    let req = QueryArticle {
+        page_size: 1,
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req.clone()).await.unwrap();
+    assert_eq!(res[0].title, "c_page".to_string());
+
+    let cursor = Cursor::from_article(&res[0], SortKey::CreatedAt).encode();
+    let req = QueryArticle { cursor, ..req };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, "a_page".to_string());
+
+    // a cursor of another sort key is rejected
+    let req = QueryArticle {
+        sort_key: SortKey::Id as i32,
+        cursor: Cursor::from_article(&res[0], SortKey::Title).encode(),
+        ..QueryArticle::default()
+    };
+    assert!(db.query_articles(req).await.is_err());
+}
+
+#[tokio::test]
+async fn query_article_projection_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    query_article_projection(db).await;
+}
+
+#[tokio::test]
+async fn query_article_projection_should_work_in_memory() {
+    query_article_projection(MemoryDB::sample()).await;
+}
+
+async fn query_article_projection<DB: BlogDB>(db: DB) {
+    let req = QueryArticle {
+        ids: vec![1000],
+        projection: ArticleProjection::Summary as i32,
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, "test_title".to_string());
+    assert_eq!(res[0].summary, "test_summary".to_string());
+    assert_eq!(res[0].tags_id, vec![1, 2]);
+    assert!(res[0].content.is_empty());
+    assert!(res[0].created_at.is_some());
+
+    let req = QueryArticle {
+        ids: vec![1000],
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].content, "test_content".to_string());
+}
+
+#[tokio::test]
+async fn search_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    db.rebuild_search_index().await.unwrap();
+    search(db).await;
+}
+
+#[tokio::test]
+async fn search_should_work_in_memory() {
+    search(MemoryDB::sample()).await;
+}
+
+async fn search<DB: BlogDB>(db: DB) {
+    let tag_id = db
+        .add_tag(util_pb::Tag {
+            name: "并发".to_string(),
+            ..util_pb::Tag::default()
+        })
+        .await
+        .unwrap();
+    let req = util_pb::Article {
+        title: "Rust 异步编程入门".to_string(),
+        content: "本文介绍 tokio 运行时，以及 async/await 的基本用法。".to_string(),
+        category_id: 1,
+        tags_id: vec![tag_id],
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+    let req = util_pb::Article {
+        title: "数据库笔记".to_string(),
+        content: "顺带提一句，异步的数据库驱动有 sqlx。".to_string(),
+        category_id: 1,
+        ..util_pb::Article::default()
+    };
+    let other_id = db.add_article(req).await.unwrap();
+
+    // title match ranks first
+    let req = SearchRequest {
+        query: "异步编程".to_string(),
+        ..SearchRequest::default()
+    };
+    let res = db.search(req).await.unwrap();
+    assert_eq!(res.total, 2);
+    assert_eq!(res.hits[0].article.as_ref().unwrap().id, id);
+    assert_eq!(res.hits[0].title, "Rust <mark>异步编程</mark>入门");
+    assert!(res.hits[1].snippet.contains("<mark>异步</mark>"));
+    assert!(res.hits[0].article.as_ref().unwrap().content.is_empty());
+
+    // content, case-insensitive
+    let req = SearchRequest {
+        query: "TOKIO".to_string(),
+        ..SearchRequest::default()
+    };
+    let res = db.search(req).await.unwrap();
+    assert_eq!(res.total, 1);
+    assert!(res.hits[0].snippet.contains("<mark>tokio</mark>"));
+
+    // tag name
+    let req = SearchRequest {
+        query: "并发".to_string(),
+        ..SearchRequest::default()
+    };
+    let res = db.search(req).await.unwrap();
+    assert_eq!(res.hits[0].article.as_ref().unwrap().id, id);
+
+    // fixture articles indexed by the rebuild
+    let req = SearchRequest {
+        query: "test_content1".to_string(),
+        ..SearchRequest::default()
+    };
+    let res = db.search(req).await.unwrap();
+    assert_eq!(res.hits[0].article.as_ref().unwrap().id, 1001);
+
+    // paging
+    let mut ids = vec![];
+    for page in 0..3 {
+        let req = SearchRequest {
+            query: "异步".to_string(),
+            page_size: 1,
+            page,
+            ..SearchRequest::default()
+        };
+        let res = db.search(req).await.unwrap();
+        assert_eq!(res.total, 2);
+        ids.extend(res.hits.iter().map(|hit| hit.article.as_ref().unwrap().id));
+    }
+    ids.sort();
+    assert_eq!(ids, vec![id, other_id]);
+
+    // kept in sync by edit and delete
+    let req = util_pb::Article {
+        id,
+        content: "改成讲 channel 了。".to_string(),
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+    let req = SearchRequest {
+        query: "tokio".to_string(),
+        ..SearchRequest::default()
+    };
+    assert_eq!(db.search(req).await.unwrap().total, 0);
+
+    db.delete_article(other_id).await.unwrap();
+    let req = SearchRequest {
+        query: "sqlx".to_string(),
+        ..SearchRequest::default()
+    };
+    assert_eq!(db.search(req).await.unwrap().total, 0);
+}
+
+#[tokio::test]
+async fn article_revisions_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    article_revisions(db).await;
+}
+
+#[tokio::test]
+async fn article_revisions_should_work_in_memory() {
+    article_revisions(MemoryDB::sample()).await;
+}
+
+async fn article_revisions<DB: BlogDB>(db: DB) {
+    let req = util_pb::Article {
+        id: 1000,
+        title: "first edit".to_string(),
+        content: "first content".to_string(),
+        tags_id: vec![1],
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+    let req = util_pb::Article {
+        id: 1000,
+        title: "second edit".to_string(),
+        tags_id: vec![1],
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+
+    let revisions = db.list_revisions(1000).await.unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0].title, "first edit".to_string());
+    assert_eq!(revisions[1].title, "test_title".to_string());
+    assert_eq!(revisions[1].content, "test_content".to_string());
+    assert_eq!(revisions[1].summary, "test_summary".to_string());
+    assert_eq!(revisions[1].category_id, 1);
+    assert_eq!(revisions[1].tags_id, vec![1, 2]);
+
+    // restore the original
+    let id = db.restore_revision(revisions[1].id).await.unwrap();
+    assert_eq!(id, 1000);
+
+    let req = QueryArticle {
+        ids: vec![1000],
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, "test_title".to_string());
+    assert_eq!(res[0].content, "test_content".to_string());
+    assert_eq!(res[0].summary, "test_summary".to_string());
+    assert_eq!(res[0].tags_id, vec![1, 2]);
+
+    let revisions = db.list_revisions(1000).await.unwrap();
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[0].title, "second edit".to_string());
+
+    assert!(db.restore_revision(9999).await.is_err());
+
+    // revisions go away with their article, once it leaves the trash bin
+    db.delete_article(1000).await.unwrap();
+    assert_eq!(db.list_revisions(1000).await.unwrap().len(), 3);
+    db.purge_article(1000).await.unwrap();
+    assert!(db.list_revisions(1000).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn restore_revision_should_keep_the_state() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    restore_revision(db).await;
+}
+
+#[tokio::test]
+async fn restore_revision_should_keep_the_state_in_memory() {
+    restore_revision(MemoryDB::sample()).await;
+}
+
+async fn restore_revision<DB: BlogDB>(db: DB) {
+    let req = util_pb::Article {
+        title: "draft".to_string(),
+        content: "draft content".to_string(),
+        category_id: 1,
+        ..util_pb::Article::default()
+    };
+    let id = db.add_article(req).await.unwrap();
+    let req = util_pb::Article {
+        id,
+        title: "published".to_string(),
+        state: ArticleState::Published as i32,
+        ..util_pb::Article::default()
+    };
+    db.edit_article(req, None).await.unwrap();
+
+    // published cannot go back to draft, so the state of the revision is left out
+    let revisions = db.list_revisions(id).await.unwrap();
+    assert_eq!(revisions[0].state, ArticleState::Draft as i32);
+    db.restore_revision(revisions[0].id).await.unwrap();
+
+    let req = QueryArticle {
+        ids: vec![id],
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req).await.unwrap();
+    assert_eq!(res[0].title, "draft".to_string());
+    assert_eq!(res[0].state, ArticleState::Published as i32);
+}
+
+#[tokio::test]
+async fn archive_should_follow_timezone() {
+    let tdb = load_test_db().await;
+    let pool = tdb.get_pool().await;
+    let db = DBPool::new(pool);
+
+    sqlx::query(
+        "UPDATE blog.articles SET created_at = CASE id \
+        WHEN 1000 THEN '2024-02-29 23:30:00+00'::TIMESTAMPTZ \
+        ELSE '2024-12-31 23:00:00+00'::TIMESTAMPTZ END;",
+    )
+    .execute(db.deref())
+    .await
+    .unwrap();
+
+    let buckets = |timezone: &str| {
+        let req = ArchiveRequest {
+            timezone: timezone.to_string(),
+            ..ArchiveRequest::default()
+        };
+        let db = &db;
+        async move {
+            db.archive(req)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|b| (b.year, b.month, b.count))
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(buckets("").await, vec![(2024, 12, 1), (2024, 2, 1)]);
+    assert_eq!(
+        buckets("Asia/Shanghai").await,
+        vec![(2025, 1, 1), (2024, 3, 1)]
+    );
+
+    let ids = |year: i32, month: i32, timezone: &str| {
+        let req = QueryArticle {
+            year,
+            month,
+            timezone: timezone.to_string(),
+            ..QueryArticle::default()
+        };
+        let db = &db;
+        async move {
+            let res = db.query_articles(req).await.unwrap();
+            res.into_iter().map(|a| a.id).collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(ids(2024, 2, "UTC").await, vec![1000]);
+    assert!(ids(2024, 3, "UTC").await.is_empty());
+    assert_eq!(ids(2024, 3, "Asia/Shanghai").await, vec![1000]);
+    assert_eq!(ids(2024, 0, "Asia/Shanghai").await, vec![1000]);
+    assert_eq!(ids(2025, 1, "Asia/Shanghai").await, vec![1001]);
+
+    // the end date is inclusive
+    let range = |timezone: &str| QueryArticle {
+        from_date: "2024-03-01".to_string(),
+        to_date: "2024-12-31".to_string(),
+        timezone: timezone.to_string(),
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(range("UTC")).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1001]);
+    let res = db.query_articles(range("Asia/Shanghai")).await.unwrap();
+    assert_eq!(res.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1000]);
+
+    for req in [
+        QueryArticle {
+            year: 2024,
+            month: 13,
+            ..QueryArticle::default()
+        },
+        QueryArticle {
+            month: 2,
+            ..QueryArticle::default()
+        },
+        QueryArticle {
+            from_date: "2024/03/01".to_string(),
+            ..QueryArticle::default()
+        },
+    ] {
+        let res = db.query_articles(req).await;
+        assert!(matches!(res, Err(BackendError::InvalidRequest(_))));
+    }
+}
+
+#[tokio::test]
+async fn unknown_timezone_should_be_rejected() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    unknown_timezone(db).await;
+}
+
+#[tokio::test]
+async fn unknown_timezone_should_be_rejected_in_memory() {
+    unknown_timezone(MemoryDB::sample()).await;
+}
+
+async fn unknown_timezone<DB: BlogDB>(db: DB) {
+    let timezone = "Mars/Olympus_Mons".to_string();
+    let req = QueryArticle {
+        year: 2024,
+        timezone: timezone.clone(),
+        ..QueryArticle::default()
+    };
+    let res = db.query_articles(req.clone()).await;
+    assert!(matches!(res, Err(BackendError::InvalidRequest(_))));
+    let res = db.count_articles(req).await;
+    assert!(matches!(res, Err(BackendError::InvalidRequest(_))));
+
+    let req = ArchiveRequest {
+        timezone,
+        ..ArchiveRequest::default()
+    };
+    let res = db.archive(req).await;
+    assert!(matches!(res, Err(BackendError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn counts_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    counts(db).await;
+}
+
+#[tokio::test]
+async fn counts_should_work_in_memory() {
+    counts(MemoryDB::sample()).await;
+}
+
+async fn counts<DB: BlogDB + Clone>(db: DB) {
+    let child = util_pb::Category {
+        name: "child".to_string(),
+        parent_id: Some(1),
+        ..util_pb::Category::default()
+    };
+    let child = db.add_category(child).await.unwrap();
+    let hidden = util_pb::Article {
+        title: "hidden".to_string(),
+        state: ArticleState::Hidden as i32,
+        category_id: child,
+        tags_id: vec![1],
+        ..util_pb::Article::default()
+    };
+    db.add_article(hidden).await.unwrap();
+    let orphan = util_pb::Tag {
+        name: "orphan".to_string(),
+        ..util_pb::Tag::default()
+    };
+    let orphan = db.add_tag(orphan).await.unwrap();
+
+    let categories = |db: &DB| {
+        let db = db.clone();
+        async move {
+            db.query_categories(QueryCategory::default())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.id, c.published_count, c.total_count))
+                .collect::<Vec<_>>()
+        }
+    };
+    let tags = |db: &DB| {
+        let db = db.clone();
+        async move {
+            db.query_tags(QueryTag::default())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|t| (t.id, t.published_count, t.total_count))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // a category counts the articles of its descendants
+    assert_eq!(
+        categories(&db).await,
+        vec![(0, 0, 0), (1, 2, 3), (child, 0, 1)]
+    );
+    assert_eq!(
+        tags(&db).await,
+        vec![(0, 0, 0), (1, 1, 2), (2, 2, 2), (orphan, 0, 0)]
+    );
+
+    // articles in the trash bin are not counted
+    db.delete_article(1001).await.unwrap();
+    assert_eq!(
+        categories(&db).await,
+        vec![(0, 0, 0), (1, 1, 2), (child, 0, 1)]
+    );
+    assert_eq!(
+        tags(&db).await,
+        vec![(0, 0, 0), (1, 1, 2), (2, 1, 1), (orphan, 0, 0)]
+    );
+
+    // filters still apply
+    let req = QueryTag {
+        ids: vec![2],
+        ..QueryTag::default()
+    };
+    let res = db.query_tags(req).await.unwrap();
+    assert_eq!((res[0].published_count, res[0].total_count), (1, 1));
+}
+
+#[tokio::test]
+async fn tag_match_modes_should_work() {
+    let tdb = load_test_db().await;
+    let db = DBPool::new(tdb.get_pool().await);
+    tag_match_modes(db).await;
+}
+
+#[tokio::test]
+async fn tag_match_modes_should_work_in_memory() {
+    tag_match_modes(MemoryDB::sample()).await;
+}
+
+async fn tag_match_modes<DB: BlogDB>(db: DB) {
+    // article 1000 carries tags 1 and 2, article 1001 only tag 2
+    let cases = [
+        (TagMatch::All, vec![1, 2], vec![], vec![1000]),
+        (TagMatch::All, vec![2, 2], vec![], vec![1000, 1001]),
+        (TagMatch::All, vec![99], vec![], vec![]),
+        // the tags restrict the other filters rather than adding to them
+        (TagMatch::All, vec![1, 2], vec![1001], vec![]),
+        (TagMatch::Any, vec![1], vec![], vec![1000]),
+        (TagMatch::Any, vec![1, 99], vec![], vec![1000]),
+        (TagMatch::Any, vec![1, 2], vec![], vec![1000, 1001]),
+        (TagMatch::Any, vec![1], vec![1001], vec![]),
+        (TagMatch::None, vec![1], vec![], vec![1001]),
+        (TagMatch::None, vec![2], vec![], vec![]),
+        (TagMatch::None, vec![99], vec![1000], vec![1000]),
+    ];
+    for (tag_match, tags_id, ids, expected) in cases {
+        let req = QueryArticle {
+            ids,
+            tags_id,
+            tag_match: tag_match as i32,
+            ..QueryArticle::default()
+        };
+        let mut res: Vec<i32> = db
+            .query_articles(req.clone())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|article| article.id)
+            .collect();
+        res.sort_unstable();
+        assert_eq!(res, expected, "{:?}", req);
+        assert_eq!(
+            db.count_articles(req.clone()).await.unwrap(),
+            expected.len() as i64,
+            "{:?}",
+            req
+        );
+    }
 }
@@ -1,36 +1,106 @@
-use util_pb::{Article, Category, QueryArticle, QueryCategory, QueryTag, Tag};
+use chrono::{DateTime, Local};
+use util_pb::{
+    ArchiveBucket, ArchiveRequest, Article, ArticleRevision, BatchOperation, Category, FieldMask,
+    QueryArticle, QueryCategory, QueryTag, SearchRequest, SearchResponse, Tag,
+};
 
 use crate::Result;
 
 pub type ID = i32;
 
 #[tonic::async_trait]
-pub trait BlogDB {
-    async fn query_articles(&self, mut req: QueryArticle) -> Result<Vec<Article>>;
+pub trait BlogDB: Send + Sync {
+    async fn query_articles(&self, req: QueryArticle) -> Result<Vec<Article>>;
+
+    /// Number of articles matching the filters of `req`, regardless of its page.
+    async fn count_articles(&self, req: QueryArticle) -> Result<i64>;
 
     async fn add_article(&self, article: Article) -> Result<ID>;
 
-    async fn edit_article(&self, article: Article) -> Result<ID>;
+    /// Write the fields listed in `mask`, empty ones included, or without a mask the
+    /// non-empty fields and the whole tag set.
+    async fn edit_article(&self, article: Article, mask: Option<FieldMask>) -> Result<ID>;
 
+    /// Move the article to the trash bin.
     async fn delete_article(&self, id: ID) -> Result<()>;
 
+    /// Take the article out of the trash bin.
+    async fn restore_article(&self, id: ID) -> Result<()>;
+
+    /// Delete the article for good; it must be in the trash bin already.
+    async fn purge_article(&self, id: ID) -> Result<()>;
+
+    /// Versions of an article saved by `edit_article`, newest first.
+    async fn list_revisions(&self, article_id: ID) -> Result<Vec<ArticleRevision>>;
+
+    /// Edit the article back to the content, category and tags of a revision, leaving its state
+    /// alone; the replaced version becomes a revision too.
+    async fn restore_revision(&self, revision_id: ID) -> Result<ID>;
+
     async fn query_categories(&self, req: QueryCategory) -> Result<Vec<Category>>;
 
     async fn add_category(&self, category: Category) -> Result<ID>;
 
-    async fn edit_category(&self, category: Category) -> Result<ID>;
+    /// Write the name and the parent listed in `mask`, or without a mask the name and a given
+    /// parent; a category cannot be moved under its own subcategories.
+    async fn edit_category(&self, category: Category, mask: Option<FieldMask>) -> Result<ID>;
 
-    async fn delete_category(&self, id: ID) -> Result<()>;
+    /// Move the category to the trash bin, its articles to `reassign_to` or else category 0.
+    async fn delete_category(&self, id: ID, reassign_to: Option<ID>) -> Result<()>;
+
+    /// Take the category out of the trash bin.
+    async fn restore_category(&self, id: ID) -> Result<()>;
+
+    /// Delete the category for good; it must be in the trash bin already. Articles still in it
+    /// go to category 0.
+    async fn purge_category(&self, id: ID) -> Result<()>;
 
     async fn query_tags(&self, req: QueryTag) -> Result<Vec<Tag>>;
 
     async fn add_tag(&self, tag: Tag) -> Result<ID>;
 
-    async fn edit_tag(&self, tag: Tag) -> Result<ID>;
+    /// Rename the tag unless `mask` leaves the name out.
+    async fn edit_tag(&self, tag: Tag, mask: Option<FieldMask>) -> Result<ID>;
 
+    /// Move the tag to the trash bin. Articles left without a tag out of it get tag 0.
     async fn delete_tag(&self, id: ID) -> Result<()>;
 
+    /// Take the tag out of the trash bin, and tag 0 off the articles it tags again.
+    async fn restore_tag(&self, id: ID) -> Result<()>;
+
+    /// Delete the tag for good; it must be in the trash bin already. Articles left without a tag
+    /// get tag 0.
+    async fn purge_tag(&self, id: ID) -> Result<()>;
+
+    /// Re-tag the articles of `source_ids` with `target_id`, delete the sources and keep their
+    /// names as aliases of the target. Returns the number of articles re-tagged.
+    async fn merge_tags(&self, source_ids: Vec<ID>, target_id: ID) -> Result<i32>;
+
+    /// Ids of the tags named, matched case-insensitively and through aliases, creating those
+    /// missing. Blank names are skipped and each id comes once, in the order of the names.
+    async fn resolve_tags(&self, names: Vec<String>) -> Result<Vec<ID>>;
+
+    /// Ids of the tags named, matched as `resolve_tags` does but creating none; then the names
+    /// of no tag.
+    async fn find_tags(&self, names: Vec<String>) -> Result<(Vec<ID>, Vec<String>)>;
+
     async fn tag_to_articles(&self, tag_id: ID) -> Result<Vec<ID>>;
 
     async fn article_to_tags(&self, article_id: ID) -> Result<Vec<ID>>;
+
+    /// Article counts by month of creation in the timezone of `req`, newest first.
+    async fn archive(&self, req: ArchiveRequest) -> Result<Vec<ArchiveBucket>>;
+
+    /// Run `operations` in one transaction, each in a savepoint: the result of every one of
+    /// them, the failed ones rolled back alone.
+    async fn batch(&self, operations: Vec<BatchOperation>) -> Result<Vec<Result<ID>>>;
+
+    /// Publish the scheduled articles whose `publish_at` is not after `now`.
+    async fn publish_due(&self, now: DateTime<Local>) -> Result<Vec<ID>>;
+
+    /// Hide the published articles whose `expire_at` is not after `now`.
+    async fn expire_due(&self, now: DateTime<Local>) -> Result<Vec<ID>>;
+
+    /// Ranked full-text search over titles, summaries, contents and tag names.
+    async fn search(&self, req: SearchRequest) -> Result<SearchResponse>;
 }
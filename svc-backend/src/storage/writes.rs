@@ -0,0 +1,687 @@
+use std::collections::HashSet;
+
+use chrono_tz::Tz;
+use sqlx::{Acquire, Executor, Postgres, QueryBuilder, Row, Transaction};
+
+use util_pb::create_request::Create;
+use util_pb::delete_request::Delete;
+use util_pb::transfer::{
+    check_mask, timezone_or_utc, to_chrono, Masked, ARTICLE_FIELDS, AS, CATEGORY_FIELDS,
+    NAME_FIELDS,
+};
+use util_pb::update_request::Update;
+use util_pb::{
+    batch_operation::Operation, get_summary, transfer::ToSql, Article, ArticleState,
+    BatchOperation, Category, FieldMask, Tag,
+};
+
+use crate::error::Result;
+use crate::search;
+use crate::storage::traits::ID;
+use crate::BackendError;
+
+/* =================================================================
+
+
+Writes inside a transaction of the caller
+
+
+================================================================== */
+/// Run each of `operations` in a savepoint of `tx`, so a failed one is rolled back alone.
+pub(super) async fn batch(
+    tx: &mut Transaction<'_, Postgres>,
+    operations: Vec<BatchOperation>,
+) -> Result<Vec<Result<ID>>> {
+    let mut results = Vec::with_capacity(operations.len());
+    for operation in operations {
+        let mut savepoint = tx.begin().await?;
+        let res = apply(&mut savepoint, operation).await;
+        if res.is_ok() {
+            savepoint.commit().await?;
+        } else {
+            savepoint.rollback().await?;
+        }
+        results.push(res);
+    }
+    Ok(results)
+}
+
+/// One operation of a batch, as the Create, Update and Delete RPCs would do it.
+async fn apply(tx: &mut Transaction<'_, Postgres>, operation: BatchOperation) -> Result<ID> {
+    let empty = || BackendError::InvalidRequest("Empty batch operation.".to_string());
+    match operation.operation.ok_or_else(empty)? {
+        Operation::Create(req) => match req.create.ok_or_else(empty)? {
+            Create::Article(article) => add_article(tx, article).await,
+            Create::Category(category) => add_category(tx, category).await,
+            Create::Tag(tag) => add_tag(tx, tag).await,
+        },
+        Operation::Update(req) => match req.update.ok_or_else(empty)? {
+            Update::Article(article) => edit_article(tx, article, req.update_mask).await,
+            Update::Category(category) => edit_category(tx, category, req.update_mask).await,
+            Update::Tag(tag) => edit_tag(tx, tag, req.update_mask).await,
+        },
+        Operation::Delete(req) => match req.delete.ok_or_else(empty)? {
+            Delete::ArticleId(id) if req.purge => purge_article(tx, id).await.map(|_| id),
+            Delete::ArticleId(id) => move_to_trash(&mut *tx, "blog.articles", id, true)
+                .await
+                .map(|_| id),
+            Delete::CategoryId(id) if req.purge => purge_category(tx, id).await.map(|_| id),
+            Delete::CategoryId(id) => delete_category(tx, id, req.reassign_to).await.map(|_| id),
+            Delete::TagId(id) if req.purge => purge_tag(tx, id).await.map(|_| id),
+            Delete::TagId(id) => delete_tag(tx, id).await.map(|_| id),
+        },
+    }
+}
+
+pub(super) async fn add_article(
+    tx: &mut Transaction<'_, Postgres>,
+    article: Article,
+) -> Result<ID> {
+    let summary = if article.summary.is_empty() {
+        get_summary(&article.content)
+    } else {
+        article.summary
+    };
+
+    let state = match ArticleState::from_i32(article.state) {
+        Some(ArticleState::Unspecified) => AS::Draft,
+        _ => AS::try_from(article.state).map_err(BackendError::InvalidRequest)?,
+    };
+    if matches!(state, AS::Scheduled) && article.publish_at.is_none() {
+        return Err(unscheduled());
+    }
+
+    // step1: bolg.articles
+    let sql = "INSERT INTO blog.articles (title, content, summary, state, category_id, publish_at, expire_at) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id;";
+
+    let id = sqlx::query(sql)
+        .bind(&article.title)
+        .bind(&article.content)
+        .bind(&summary)
+        .bind(state)
+        .bind(article.category_id)
+        .bind(article.publish_at.as_ref().map(to_chrono))
+        .bind(article.expire_at.as_ref().map(to_chrono))
+        .fetch_one(&mut *tx)
+        .await?
+        .get(0);
+    index_article(tx, id, &article.title, &summary, &article.content).await?;
+
+    // step2: blog.article_tag
+    link_tags(tx, id, &article.tags_id).await?;
+    fall_back_to_default_tag(&mut *tx, &[id]).await?;
+
+    Ok(id)
+}
+
+pub(super) async fn edit_article(
+    tx: &mut Transaction<'_, Postgres>,
+    article: Article,
+    mask: Option<FieldMask>,
+) -> Result<ID> {
+    let paths = mask.map(|mask| mask.paths);
+    if let Some(paths) = &paths {
+        check_mask(paths, &ARTICLE_FIELDS).map_err(BackendError::InvalidRequest)?;
+    }
+    let touches = |field: &str| match &paths {
+        Some(paths) => paths.iter().any(|path| path == field),
+        None => field != "state" || article.state != ArticleState::Unspecified as i32,
+    };
+
+    let tags_id = article.tags_id.clone();
+    let article_id = article.id;
+
+    let sql = "SELECT state, version FROM blog.articles WHERE id = $1 FOR UPDATE;";
+    let row = sqlx::query(sql)
+        .bind(article_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let (from, version): (AS, i32) = (row.get(0), row.get(1));
+    if article.version != 0 && article.version != version {
+        return Err(BackendError::Conflict(format!(
+            "The article was edited in the meantime, version {} is now {}.",
+            article.version, version
+        )));
+    }
+
+    if touches("state") {
+        let to = AS::try_from(article.state).map_err(BackendError::InvalidRequest)?;
+        if !from.can_become(to) {
+            return Err(BackendError::InvalidRequest(format!(
+                "An article cannot go from {} to {}.",
+                from.name(),
+                to.name()
+            )));
+        }
+    }
+
+    // step0: keep the current version
+    let sql = "INSERT INTO blog.article_revisions (article_id, title, content, summary, state, category_id, tags_id) \
+        SELECT id, title, content, summary, state, category_id, \
+        ARRAY(SELECT tag_id FROM blog.article_tag WHERE article_id = $1) \
+        FROM blog.articles WHERE id = $1;";
+    sqlx::query(sql).bind(article_id).execute(&mut *tx).await?;
+
+    // step1
+    let touches_tags = touches("tags_id");
+    let mut builder = QueryBuilder::<Postgres>::new("UPDATE blog.articles SET ");
+    match &paths {
+        Some(paths) => Masked {
+            value: article,
+            paths,
+        }
+        .to_sql(&mut builder),
+        None => article.to_sql(&mut builder),
+    }
+    builder
+        .push(", version = version + 1 WHERE id = ")
+        .push_bind(article_id)
+        .push(
+            " RETURNING id, title, summary, content, state = 'scheduled' AND publish_at IS NULL;",
+        );
+    let row = builder.build().fetch_one(&mut *tx).await?;
+    if row.get::<bool, _>(4) {
+        return Err(unscheduled());
+    }
+    let id = row.get(0);
+    index_article(tx, id, row.get(1), row.get(2), row.get(3)).await?;
+
+    if !touches_tags {
+        return Ok(id);
+    }
+
+    // step2, leaving the links to the tags in the trash bin alone
+    let sql = "SELECT tag_id FROM blog.article_tag JOIN blog.tags ON id = tag_id WHERE article_id = $1 AND deleted_at IS NULL;";
+    let old_tags = sqlx::query(sql)
+        .bind(article_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<i32, _>(0));
+    let old_tags = HashSet::<_>::from_iter(old_tags);
+    let new_tags = HashSet::<_>::from_iter(tags_id);
+
+    let need_add_tags = new_tags
+        .difference(&old_tags)
+        .cloned()
+        .collect::<Vec<i32>>();
+    let need_delete_tags = old_tags
+        .difference(&new_tags)
+        .cloned()
+        .collect::<Vec<i32>>();
+
+    link_tags(tx, id, &need_add_tags).await?;
+
+    let sql = "DELETE FROM blog.article_tag WHERE article_id = $1 AND tag_id = ANY($2);";
+    sqlx::query(sql)
+        .bind(id)
+        .bind(&need_delete_tags)
+        .execute(&mut *tx)
+        .await?;
+    fall_back_to_default_tag(&mut *tx, &[id]).await?;
+
+    Ok(id)
+}
+
+pub(super) async fn purge_article(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_in_trash(&mut *tx, "blog.articles", id).await?;
+
+    // step2
+    let sql = "DELETE FROM blog.article_tag WHERE article_id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+    let sql = "DELETE FROM blog.article_terms WHERE article_id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+    let sql = "DELETE FROM blog.article_revisions WHERE article_id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    // step1
+    let sql = "DELETE FROM blog.articles WHERE id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    Ok(())
+}
+
+pub(super) async fn add_category(
+    tx: &mut Transaction<'_, Postgres>,
+    category: Category,
+) -> Result<ID> {
+    let sql = "INSERT INTO blog.categories (name, parent_id) VALUES ($1, $2) RETURNING id;";
+    let id = sqlx::query(sql)
+        .bind(&category.name)
+        .bind(category.parent_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get(0);
+    Ok(id)
+}
+
+pub(super) async fn edit_category(
+    tx: &mut Transaction<'_, Postgres>,
+    category: Category,
+    mask: Option<FieldMask>,
+) -> Result<ID> {
+    let paths = mask.map(|mask| mask.paths);
+    if let Some(paths) = &paths {
+        check_mask(paths, &CATEGORY_FIELDS).map_err(BackendError::InvalidRequest)?;
+    }
+    let touches = |field: &str| match &paths {
+        Some(paths) => paths.iter().any(|path| path == field),
+        None => field == "name" || category.parent_id.is_some(),
+    };
+
+    if touches("name") || touches("parent_id") {
+        ensure_not_default("blog.categories", category.id, "edited")?;
+    }
+
+    if touches("name") {
+        ensure_name_unused(&mut *tx, "blog.categories", &category.name, category.id).await?;
+    }
+
+    if let (true, Some(parent_id)) = (touches("parent_id"), category.parent_id) {
+        ensure_acyclic(&mut *tx, category.id, parent_id).await?;
+    }
+
+    let mut builder = QueryBuilder::<Postgres>::new("UPDATE blog.categories SET id = id");
+    if touches("name") {
+        builder.push(", name = ").push_bind(category.name);
+    }
+    if touches("parent_id") {
+        builder.push(", parent_id = ").push_bind(category.parent_id);
+    }
+    builder
+        .push(" WHERE id = ")
+        .push_bind(category.id)
+        .push(" RETURNING id;");
+    let id = builder.build().fetch_one(&mut *tx).await?.get(0);
+
+    Ok(id)
+}
+
+pub(super) async fn delete_category(
+    tx: &mut Transaction<'_, Postgres>,
+    id: ID,
+    reassign_to: Option<ID>,
+) -> Result<()> {
+    ensure_not_default("blog.categories", id, "deleted")?;
+    let reassign_to = reassign_to.unwrap_or(0);
+
+    let sql = "SELECT id FROM blog.categories WHERE id = $1 AND id <> $2 AND deleted_at IS NULL FOR SHARE;";
+    sqlx::query(sql)
+        .bind(reassign_to)
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            BackendError::InvalidRequest(format!(
+                "Cannot move the articles of category {} to {}.",
+                id, reassign_to
+            ))
+        })?;
+    let sql = "UPDATE blog.articles SET category_id = $1 WHERE category_id = $2;";
+    sqlx::query(sql)
+        .bind(reassign_to)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    move_to_trash(&mut *tx, "blog.categories", id, true).await?;
+
+    Ok(())
+}
+
+pub(super) async fn purge_category(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_in_trash(&mut *tx, "blog.categories", id).await?;
+
+    // articles given the category while it was in the trash bin
+    let sql = "UPDATE blog.articles SET category_id = 0 WHERE category_id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    let sql = "Delete FROM blog.categories WHERE id = $1 RETURNING id;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    Ok(())
+}
+
+pub(super) async fn add_tag(tx: &mut Transaction<'_, Postgres>, tag: Tag) -> Result<ID> {
+    // another spelling of a tag, or the name of one merged into it, stands for that tag
+    let name = tag.name.trim();
+    if name.is_empty() {
+        return Err(BackendError::InvalidRequest(
+            "A tag needs a name.".to_string(),
+        ));
+    }
+    resolve_tag(tx, name).await
+}
+
+/// The tag `name` stands for as `find_tag` finds it, created when there is none.
+pub(super) async fn resolve_tag(tx: &mut Transaction<'_, Postgres>, name: &str) -> Result<ID> {
+    if let Some(id) = find_tag(&mut *tx, name).await? {
+        return Ok(id);
+    }
+
+    // a concurrent request may create it first, and then it is found again
+    let sql = "INSERT INTO blog.tags (name) VALUES ($1) ON CONFLICT DO NOTHING RETURNING id;";
+    if let Some(row) = sqlx::query(sql).bind(name).fetch_optional(&mut *tx).await? {
+        return Ok(row.get(0));
+    }
+    find_tag(&mut *tx, name)
+        .await?
+        .ok_or(BackendError::SqlxError(sqlx::Error::RowNotFound))
+}
+
+/// The tag `name` stands for, ignoring case: a tag of that name, or the one an alias was merged
+/// into. The exact spelling wins over the others, then a tag over an alias.
+pub(super) async fn find_tag(tx: &mut Transaction<'_, Postgres>, name: &str) -> Result<Option<ID>> {
+    let sql = "SELECT id FROM (\
+        SELECT id, name = $1 AS exact, 0 AS rank FROM blog.tags WHERE LOWER(name) = LOWER($1) AND deleted_at IS NULL \
+        UNION ALL \
+        SELECT tag_id, name = $1, 1 FROM blog.tag_aliases WHERE LOWER(name) = LOWER($1)\
+        ) AS found ORDER BY exact DESC, rank, id LIMIT 1;";
+    let row = sqlx::query(sql).bind(name).fetch_optional(&mut *tx).await?;
+    Ok(row.map(|row| row.get(0)))
+}
+
+pub(super) async fn edit_tag(
+    tx: &mut Transaction<'_, Postgres>,
+    tag: Tag,
+    mask: Option<FieldMask>,
+) -> Result<ID> {
+    if !touches_name(mask)? {
+        let sql = "SELECT id FROM blog.tags WHERE id = $1;";
+        let id = sqlx::query(sql)
+            .bind(tag.id)
+            .fetch_one(&mut *tx)
+            .await?
+            .get(0);
+        return Ok(id);
+    }
+
+    ensure_not_default("blog.tags", tag.id, "renamed")?;
+    ensure_name_unused(&mut *tx, "blog.tags", &tag.name, tag.id).await?;
+
+    // the name of a tag merged into another stands for that one
+    let sql = "SELECT tag_id FROM blog.tag_aliases WHERE LOWER(name) = LOWER($1) AND tag_id <> $2;";
+    if let Some(row) = sqlx::query(sql)
+        .bind(&tag.name)
+        .bind(tag.id)
+        .fetch_optional(&mut *tx)
+        .await?
+    {
+        return Err(alias_taken(&tag.name, row.get(0)));
+    }
+
+    let sql = "UPDATE blog.tags SET name = $1 WHERE id = $2 RETURNING id;";
+    let id = sqlx::query(sql)
+        .bind(&tag.name)
+        .bind(tag.id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get(0);
+    Ok(id)
+}
+
+pub(super) async fn delete_tag(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_not_default("blog.tags", id, "deleted")?;
+    move_to_trash(&mut *tx, "blog.tags", id, true).await?;
+
+    let sql = "SELECT article_id FROM blog.article_tag WHERE tag_id = $1;";
+    let article_ids = sqlx::query(sql)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect::<Vec<ID>>();
+    fall_back_to_default_tag(&mut *tx, &article_ids).await
+}
+
+/// Take the category out of the trash bin, unless its name was given to another one meanwhile.
+pub(super) async fn restore_category(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_name_free(&mut *tx, "blog.categories", id).await?;
+    move_to_trash(&mut *tx, "blog.categories", id, false).await
+}
+
+/// Take the tag out of the trash bin, unless its name was given to another one meanwhile, and tag
+/// 0 off the articles it gives a tag again.
+pub(super) async fn restore_tag(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_name_free(&mut *tx, "blog.tags", id).await?;
+    move_to_trash(&mut *tx, "blog.tags", id, false).await?;
+
+    let sql = "DELETE FROM blog.article_tag WHERE tag_id = 0 AND $1 <> 0 \
+        AND article_id IN (SELECT article_id FROM blog.article_tag WHERE tag_id = $1);";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+    Ok(())
+}
+
+pub(super) async fn purge_tag(tx: &mut Transaction<'_, Postgres>, id: ID) -> Result<()> {
+    ensure_in_trash(&mut *tx, "blog.tags", id).await?;
+
+    // step2
+    let sql = "DELETE FROM blog.article_tag WHERE tag_id = $1 RETURNING article_id;";
+    let article_ids = sqlx::query(sql)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect::<Vec<ID>>();
+    fall_back_to_default_tag(&mut *tx, &article_ids).await?;
+
+    // step1
+    let sql = "Delete FROM blog.tags WHERE id = $1 RETURNING id;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    Ok(())
+}
+
+pub(super) fn unscheduled() -> BackendError {
+    BackendError::InvalidRequest("A scheduled article needs a publish_at.".to_string())
+}
+
+/// Fail if `parent_id` is `id` or one of its subcategories. Moves are serialized by a table lock,
+/// so two of them cannot make a cycle together.
+async fn ensure_acyclic(tx: &mut Transaction<'_, Postgres>, id: ID, parent_id: ID) -> Result<()> {
+    sqlx::query("LOCK TABLE blog.categories IN SHARE ROW EXCLUSIVE MODE;")
+        .execute(&mut *tx)
+        .await?;
+
+    let sql = "WITH RECURSIVE ancestors AS (SELECT id, parent_id FROM blog.categories WHERE id = $1 \
+        UNION SELECT c.id, c.parent_id FROM blog.categories AS c JOIN ancestors ON c.id = ancestors.parent_id) \
+        SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2);";
+    let cyclic: bool = sqlx::query(sql)
+        .bind(parent_id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get(0);
+    if cyclic {
+        return Err(BackendError::InvalidRequest(format!(
+            "Category {} cannot be moved under {}, one of its own subcategories.",
+            id, parent_id
+        )));
+    }
+    Ok(())
+}
+
+/// Whether an update of a tag writes its name; no mask writes it.
+pub(super) fn touches_name(mask: Option<FieldMask>) -> Result<bool> {
+    match mask {
+        Some(mask) => {
+            check_mask(&mask.paths, &NAME_FIELDS).map_err(BackendError::InvalidRequest)?;
+            Ok(!mask.paths.is_empty())
+        }
+        None => Ok(true),
+    }
+}
+
+/// Set (`to_trash`) or clear `deleted_at` of a row of `table`.
+pub(super) async fn move_to_trash<'e, E>(
+    executor: E,
+    table: &str,
+    id: ID,
+    to_trash: bool,
+) -> Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let sql = if to_trash {
+        format!(
+            "UPDATE {} SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL;",
+            table
+        )
+    } else {
+        format!(
+            "UPDATE {} SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL;",
+            table
+        )
+    };
+    let res = sqlx::query(&sql).bind(id).execute(executor).await?;
+    if res.rows_affected() == 0 {
+        let place = if to_trash { "out of" } else { "in" };
+        return Err(BackendError::InvalidRequest(format!(
+            "No row {} of {} {} the trash bin.",
+            id, table, place
+        )));
+    }
+    Ok(())
+}
+
+/// The name of row `id` in the trash bin is not taken by a row out of it, which the unique index
+/// on the names would otherwise reject on the way out.
+async fn ensure_name_free(tx: &mut Transaction<'_, Postgres>, table: &str, id: ID) -> Result<()> {
+    let sql = format!(
+        "SELECT name FROM {0} AS r WHERE id = $1 AND deleted_at IS NOT NULL \
+        AND EXISTS (SELECT 1 FROM {0} WHERE name = r.name AND deleted_at IS NULL);",
+        table
+    );
+    let taken = sqlx::query(&sql).bind(id).fetch_optional(&mut *tx).await?;
+    match taken {
+        Some(row) => Err(BackendError::InvalidRequest(format!(
+            "{} has a row named {} already.",
+            table,
+            row.get::<String, _>(0)
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// No row out of the trash bin but `id` is named `name`, which the unique index on the names
+/// would otherwise reject when row `id` is renamed.
+async fn ensure_name_unused(
+    tx: &mut Transaction<'_, Postgres>,
+    table: &str,
+    name: &str,
+    id: ID,
+) -> Result<()> {
+    let sql = format!(
+        "SELECT 1 FROM {} WHERE name = $1 AND deleted_at IS NULL AND id <> $2;",
+        table
+    );
+    let taken = sqlx::query(&sql)
+        .bind(name)
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    match taken {
+        Some(_) => Err(BackendError::InvalidRequest(format!(
+            "{} has a row named {} already.",
+            table, name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// A tag renamed after an alias of another tag, which would then stand for two tags.
+pub(super) fn alias_taken(name: &str, tag_id: ID) -> BackendError {
+    BackendError::InvalidRequest(format!(
+        "{} is the name of a tag merged into tag {}.",
+        name, tag_id
+    ))
+}
+
+/// The timezone of the calendar filters of a request, checked here rather than left to Postgres.
+pub(super) fn parse_timezone(timezone: &str) -> Result<Tz> {
+    timezone_or_utc(timezone)
+        .parse()
+        .map_err(|_| BackendError::InvalidRequest(format!("No such timezone: {}", timezone)))
+}
+
+/// Category 0 and tag 0 hold what has no other category or tag, so they always stay.
+pub(super) fn ensure_not_default(table: &str, id: ID, action: &str) -> Result<()> {
+    if id == 0 {
+        return Err(BackendError::InvalidRequest(format!(
+            "Row 0 of {} is the default one and cannot be {}.",
+            table, action
+        )));
+    }
+    Ok(())
+}
+
+/// Give tag 0 to those of `article_ids` left without any tag out of the trash bin.
+async fn fall_back_to_default_tag(
+    tx: &mut Transaction<'_, Postgres>,
+    article_ids: &[ID],
+) -> Result<()> {
+    let sql = "INSERT INTO blog.article_tag (article_id, tag_id) \
+        SELECT id, 0 FROM blog.articles AS a WHERE id = ANY($1) \
+        AND NOT EXISTS (SELECT 1 FROM blog.article_tag JOIN blog.tags AS t ON t.id = tag_id \
+            WHERE article_id = a.id AND t.deleted_at IS NULL) \
+        ON CONFLICT DO NOTHING;";
+    sqlx::query(sql).bind(article_ids).execute(&mut *tx).await?;
+    Ok(())
+}
+
+/// Lock a row of `table` for deletion, failing unless it is in the trash bin.
+async fn ensure_in_trash(tx: &mut Transaction<'_, Postgres>, table: &str, id: ID) -> Result<()> {
+    let sql = format!(
+        "SELECT id FROM {} WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE;",
+        table
+    );
+    sqlx::query(&sql)
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            BackendError::InvalidRequest(format!("No row {} of {} in the trash bin.", id, table))
+        })?;
+    Ok(())
+}
+
+/// Replace the search terms of an article, inside the transaction writing it.
+pub(super) async fn index_article(
+    tx: &mut Transaction<'_, Postgres>,
+    id: ID,
+    title: &str,
+    summary: &str,
+    content: &str,
+) -> Result<()> {
+    let sql = "DELETE FROM blog.article_terms WHERE article_id = $1;";
+    sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+
+    let (terms, weights): (Vec<String>, Vec<f32>) = search::term_weights(title, summary, content)
+        .into_iter()
+        .unzip();
+    let sql = "INSERT INTO blog.article_terms (article_id, term, weight) SELECT $1, UNNEST($2::VARCHAR[]), UNNEST($3::REAL[]);";
+    sqlx::query(sql)
+        .bind(id)
+        .bind(terms)
+        .bind(weights)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
+/// Link an article to `tags_id` in one statement, keeping the links it already has.
+async fn link_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    article_id: ID,
+    tags_id: &[ID],
+) -> Result<()> {
+    let sql = "INSERT INTO blog.article_tag (article_id, tag_id) SELECT $1, UNNEST($2::INT[]) \
+        ON CONFLICT DO NOTHING;";
+    sqlx::query(sql)
+        .bind(article_id)
+        .bind(tags_id)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
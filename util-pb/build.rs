@@ -8,6 +8,10 @@ fn main() {
         .with_sqlx_from_row(&["models.Category", "models.Tag"], None)
         .with_serde(&["models.Category", "models.Tag"], true, false, None)
         .with_derive_builder(&["models.QueryArticle"], None)
+        .field_attribute("models.Category.published_count", "#[sqlx(default)]")
+        .field_attribute("models.Category.total_count", "#[sqlx(default)]")
+        .field_attribute("models.Tag.published_count", "#[sqlx(default)]")
+        .field_attribute("models.Tag.total_count", "#[sqlx(default)]")
         .compile(&["proto/models.proto"], &["."])
         .unwrap();
 
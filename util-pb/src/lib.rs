@@ -1,4 +1,5 @@
 pub use pb::models::*;
+pub use prost_types::{FieldMask, Timestamp};
 pub use transfer::to_timestamp;
 pub use util::get_summary;
 
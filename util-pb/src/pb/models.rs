@@ -1,3 +1,11 @@
+/// the states an article query matches
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArticleStateFilter {
+    /// empty for any state
+    #[prost(enumeration = "ArticleState", repeated, tag = "1")]
+    pub states: ::prost::alloc::vec::Vec<i32>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Article {
@@ -19,6 +27,40 @@ pub struct Article {
     pub category_id: i32,
     #[prost(int32, repeated, tag = "9")]
     pub tags_id: ::prost::alloc::vec::Vec<i32>,
+    /// when a SCHEDULED article gets PUBLISHED
+    #[prost(message, optional, tag = "10")]
+    pub publish_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// when a PUBLISHED article gets HIDDEN again, none for never
+    #[prost(message, optional, tag = "11")]
+    pub expire_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// bumped by every edit; an edit giving the version it was made on fails once the
+    /// article moved past it, 0 skips the check
+    #[prost(int32, tag = "12")]
+    pub version: i32,
+}
+/// the article as it was before an edit
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArticleRevision {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(int32, tag = "2")]
+    pub article_id: i32,
+    #[prost(string, tag = "3")]
+    pub title: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub content: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub summary: ::prost::alloc::string::String,
+    #[prost(enumeration = "ArticleState", tag = "6")]
+    pub state: i32,
+    #[prost(int32, tag = "7")]
+    pub category_id: i32,
+    #[prost(int32, repeated, tag = "8")]
+    pub tags_id: ::prost::alloc::vec::Vec<i32>,
+    /// when the edit replaced this version
+    #[prost(message, optional, tag = "9")]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
 }
 #[derive(sqlx::FromRow, serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -28,6 +70,17 @@ pub struct Category {
     pub id: i32,
     #[prost(string, tag = "2")]
     pub name: ::prost::alloc::string::String,
+    /// none for a root category
+    #[prost(int32, optional, tag = "3")]
+    pub parent_id: ::core::option::Option<i32>,
+    /// articles out of the trash bin in the category and its descendants, filled by queries;
+    /// published_count only counts the public ones
+    #[prost(int64, tag = "4")]
+    #[sqlx(default)]
+    pub published_count: i64,
+    #[prost(int64, tag = "5")]
+    #[sqlx(default)]
+    pub total_count: i64,
 }
 #[derive(sqlx::FromRow, serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -37,6 +90,14 @@ pub struct Tag {
     pub id: i32,
     #[prost(string, tag = "2")]
     pub name: ::prost::alloc::string::String,
+    /// articles out of the trash bin with the tag, filled by queries;
+    /// published_count only counts the public ones
+    #[prost(int64, tag = "3")]
+    #[sqlx(default)]
+    pub published_count: i64,
+    #[prost(int64, tag = "4")]
+    #[sqlx(default)]
+    pub total_count: i64,
 }
 #[derive(derive_builder::Builder)]
 #[builder(setter(into, strip_option), default)]
@@ -47,14 +108,47 @@ pub struct QueryArticle {
     pub ids: ::prost::alloc::vec::Vec<i32>,
     #[prost(string, tag = "2")]
     pub title: ::prost::alloc::string::String,
-    #[prost(enumeration = "ArticleState", tag = "3")]
-    pub state: i32,
-    #[prost(message, optional, tag = "4")]
-    pub created_year: ::core::option::Option<::prost_types::Timestamp>,
     #[prost(int32, tag = "5")]
     pub category_id: i32,
     #[prost(int32, repeated, tag = "6")]
     pub tags_id: ::prost::alloc::vec::Vec<i32>,
+    /// 0 means no limit
+    #[prost(int32, tag = "7")]
+    pub page_size: i32,
+    #[prost(enumeration = "SortKey", tag = "8")]
+    pub sort_key: i32,
+    #[prost(enumeration = "SortDirection", tag = "9")]
+    pub sort_direction: i32,
+    /// next_cursor of the previous page, empty for the first page
+    #[prost(string, tag = "10")]
+    pub cursor: ::prost::alloc::string::String,
+    #[prost(enumeration = "ArticleProjection", tag = "11")]
+    pub projection: i32,
+    #[prost(enumeration = "Trash", tag = "12")]
+    pub trash: i32,
+    #[prost(enumeration = "Visibility", tag = "13")]
+    pub visibility: i32,
+    #[prost(message, optional, tag = "14")]
+    pub state_filter: ::core::option::Option<ArticleStateFilter>,
+    /// with category_id, the articles of its subcategories too
+    #[prost(bool, tag = "15")]
+    pub include_descendants: bool,
+    /// created in a year, or a month of it, of the calendar of `timezone`; 0 for any
+    #[prost(int32, tag = "16")]
+    pub year: i32,
+    #[prost(int32, tag = "17")]
+    pub month: i32,
+    /// created from and to these days of `timezone` included, as YYYY-MM-DD; empty for no bound
+    #[prost(string, tag = "18")]
+    pub from_date: ::prost::alloc::string::String,
+    #[prost(string, tag = "19")]
+    pub to_date: ::prost::alloc::string::String,
+    /// IANA name of the timezone of the calendar filters, such as Asia/Shanghai; UTC when empty
+    #[prost(string, tag = "20")]
+    pub timezone: ::prost::alloc::string::String,
+    /// with tags_id, restricts the articles the other filters pick
+    #[prost(enumeration = "TagMatch", tag = "21")]
+    pub tag_match: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -63,6 +157,8 @@ pub struct QueryCategory {
     pub ids: ::prost::alloc::vec::Vec<i32>,
     #[prost(string, tag = "2")]
     pub name: ::prost::alloc::string::String,
+    #[prost(enumeration = "Trash", tag = "3")]
+    pub trash: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -71,6 +167,8 @@ pub struct QueryTag {
     pub ids: ::prost::alloc::vec::Vec<i32>,
     #[prost(string, tag = "2")]
     pub name: ::prost::alloc::string::String,
+    #[prost(enumeration = "Trash", tag = "3")]
+    pub trash: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -118,6 +216,11 @@ pub struct QueryResponse {
     pub categories: ::prost::alloc::vec::Vec<Category>,
     #[prost(message, repeated, tag = "3")]
     pub tags: ::prost::alloc::vec::Vec<Tag>,
+    /// empty when there is no more page
+    #[prost(string, tag = "4")]
+    pub next_cursor: ::prost::alloc::string::String,
+    #[prost(int64, tag = "5")]
+    pub total: i64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -147,6 +250,10 @@ pub struct CreateResponse {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdateRequest {
+    /// the fields to write, empty values included; without a mask the non-empty fields
+    /// are written, and the tags of an article replaced
+    #[prost(message, optional, tag = "4")]
+    pub update_mask: ::core::option::Option<::prost_types::FieldMask>,
     #[prost(oneof = "update_request::Update", tags = "1, 2, 3")]
     pub update: ::core::option::Option<update_request::Update>,
 }
@@ -172,6 +279,12 @@ pub struct UpdateResponse {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DeleteRequest {
+    /// delete for good instead of moving to the trash bin; only for rows already in it
+    #[prost(bool, tag = "4")]
+    pub purge: bool,
+    /// where the articles of a deleted category go, category 0 when not set
+    #[prost(int32, optional, tag = "5")]
+    pub reassign_to: ::core::option::Option<i32>,
     #[prost(oneof = "delete_request::Delete", tags = "1, 2, 3")]
     pub delete: ::core::option::Option<delete_request::Delete>,
 }
@@ -194,12 +307,216 @@ pub struct DeleteResponse {
     #[prost(int32, tag = "1")]
     pub id: i32,
 }
+/// take a row out of the trash bin
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreRequest {
+    #[prost(oneof = "restore_request::Restore", tags = "1, 2, 3")]
+    pub restore: ::core::option::Option<restore_request::Restore>,
+}
+/// Nested message and enum types in `RestoreRequest`.
+pub mod restore_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Restore {
+        #[prost(int32, tag = "1")]
+        ArticleId(i32),
+        #[prost(int32, tag = "2")]
+        CategoryId(i32),
+        #[prost(int32, tag = "3")]
+        TagId(i32),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreResponse {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+    /// 0 means the default page size
+    #[prost(int32, tag = "2")]
+    pub page_size: i32,
+    /// starts from 0
+    #[prost(int32, tag = "3")]
+    pub page: i32,
+    #[prost(enumeration = "Visibility", tag = "4")]
+    pub visibility: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchHit {
+    /// without content
+    #[prost(message, optional, tag = "1")]
+    pub article: ::core::option::Option<Article>,
+    #[prost(float, tag = "2")]
+    pub score: f32,
+    /// html escaped, matched terms wrapped in <mark>
+    #[prost(string, tag = "3")]
+    pub title: ::prost::alloc::string::String,
+    /// html escaped, matched terms wrapped in <mark>
+    #[prost(string, tag = "4")]
+    pub snippet: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub hits: ::prost::alloc::vec::Vec<SearchHit>,
+    #[prost(int64, tag = "2")]
+    pub total: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRevisionsRequest {
+    #[prost(int32, tag = "1")]
+    pub article_id: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRevisionsResponse {
+    /// newest first
+    #[prost(message, repeated, tag = "1")]
+    pub revisions: ::prost::alloc::vec::Vec<ArticleRevision>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreRevisionRequest {
+    #[prost(int32, tag = "1")]
+    pub revision_id: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreRevisionResponse {
+    #[prost(int32, tag = "1")]
+    pub article_id: i32,
+}
+/// article counts by month of creation, for the archive pages
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArchiveRequest {
+    /// as in QueryArticle
+    #[prost(string, tag = "1")]
+    pub timezone: ::prost::alloc::string::String,
+    #[prost(enumeration = "Visibility", tag = "2")]
+    pub visibility: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArchiveBucket {
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    #[prost(int32, tag = "2")]
+    pub month: i32,
+    #[prost(int32, tag = "3")]
+    pub count: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArchiveResponse {
+    /// newest month first
+    #[prost(message, repeated, tag = "1")]
+    pub buckets: ::prost::alloc::vec::Vec<ArchiveBucket>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchOperation {
+    #[prost(oneof = "batch_operation::Operation", tags = "1, 2, 3")]
+    pub operation: ::core::option::Option<batch_operation::Operation>,
+}
+/// Nested message and enum types in `BatchOperation`.
+pub mod batch_operation {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Operation {
+        #[prost(message, tag = "1")]
+        Create(super::CreateRequest),
+        #[prost(message, tag = "2")]
+        Update(super::UpdateRequest),
+        #[prost(message, tag = "3")]
+        Delete(super::DeleteRequest),
+    }
+}
+/// operations run in order in one transaction; a failed one is rolled back alone
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub operations: ::prost::alloc::vec::Vec<BatchOperation>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchResult {
+    /// the row created, updated or deleted
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    /// a google.rpc.Code, 0 when the operation succeeded
+    #[prost(int32, tag = "2")]
+    pub code: i32,
+    #[prost(string, tag = "3")]
+    pub message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchResponse {
+    /// one per operation, in the same order
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<BatchResult>,
+}
+/// move the articles of the sources to the target, then delete the sources and keep their
+/// names as aliases of the target
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MergeTagsRequest {
+    #[prost(int32, repeated, tag = "1")]
+    pub source_ids: ::prost::alloc::vec::Vec<i32>,
+    #[prost(int32, tag = "2")]
+    pub target_id: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MergeTagsResponse {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    /// articles that had one of the sources
+    #[prost(int32, tag = "2")]
+    pub articles: i32,
+}
+/// ids of the tags named, case-insensitively and through aliases, creating those missing;
+/// blank names are skipped and each id comes once, in the order of the names
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveTagsRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// create nothing: the names of no tag come back in `unknown` instead
+    #[prost(bool, tag = "2")]
+    pub lookup_only: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveTagsResponse {
+    #[prost(int32, repeated, tag = "1")]
+    pub ids: ::prost::alloc::vec::Vec<i32>,
+    /// names of no tag, only for `lookup_only`
+    #[prost(string, repeated, tag = "2")]
+    pub unknown: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum ArticleState {
-    All = 0,
+    /// not set: no change on update, DRAFT on create
+    Unspecified = 0,
     Published = 1,
     Hidden = 2,
+    /// published by the backend once publish_at is due
+    Scheduled = 3,
+    Draft = 4,
+    Archived = 5,
 }
 impl ArticleState {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -208,9 +525,202 @@ impl ArticleState {
     /// (if the ProtoBuf definition does not change) and safe for programmatic use.
     pub fn as_str_name(&self) -> &'static str {
         match self {
-            ArticleState::All => "All",
+            ArticleState::Unspecified => "ARTICLE_STATE_UNSPECIFIED",
             ArticleState::Published => "PUBLISHED",
             ArticleState::Hidden => "HIDDEN",
+            ArticleState::Scheduled => "SCHEDULED",
+            ArticleState::Draft => "DRAFT",
+            ArticleState::Archived => "ARCHIVED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ARTICLE_STATE_UNSPECIFIED" => Some(Self::Unspecified),
+            "PUBLISHED" => Some(Self::Published),
+            "HIDDEN" => Some(Self::Hidden),
+            "SCHEDULED" => Some(Self::Scheduled),
+            "DRAFT" => Some(Self::Draft),
+            "ARCHIVED" => Some(Self::Archived),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SortKey {
+    CreatedAt = 0,
+    UpdatedAt = 1,
+    Title = 2,
+    Id = 3,
+}
+impl SortKey {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SortKey::CreatedAt => "SORT_KEY_CREATED_AT",
+            SortKey::UpdatedAt => "SORT_KEY_UPDATED_AT",
+            SortKey::Title => "SORT_KEY_TITLE",
+            SortKey::Id => "SORT_KEY_ID",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SORT_KEY_CREATED_AT" => Some(Self::CreatedAt),
+            "SORT_KEY_UPDATED_AT" => Some(Self::UpdatedAt),
+            "SORT_KEY_TITLE" => Some(Self::Title),
+            "SORT_KEY_ID" => Some(Self::Id),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SortDirection {
+    Desc = 0,
+    Asc = 1,
+}
+impl SortDirection {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SortDirection::Desc => "SORT_DIRECTION_DESC",
+            SortDirection::Asc => "SORT_DIRECTION_ASC",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SORT_DIRECTION_DESC" => Some(Self::Desc),
+            "SORT_DIRECTION_ASC" => Some(Self::Asc),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ArticleProjection {
+    /// every column, content included
+    Full = 0,
+    /// everything but the content, for the list pages
+    Summary = 1,
+}
+impl ArticleProjection {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ArticleProjection::Full => "ARTICLE_PROJECTION_FULL",
+            ArticleProjection::Summary => "ARTICLE_PROJECTION_SUMMARY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ARTICLE_PROJECTION_FULL" => Some(Self::Full),
+            "ARTICLE_PROJECTION_SUMMARY" => Some(Self::Summary),
+            _ => None,
+        }
+    }
+}
+/// which rows a query sees, with respect to the trash bin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Trash {
+    Exclude = 0,
+    Include = 1,
+    Only = 2,
+}
+impl Trash {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Trash::Exclude => "TRASH_EXCLUDE",
+            Trash::Include => "TRASH_INCLUDE",
+            Trash::Only => "TRASH_ONLY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TRASH_EXCLUDE" => Some(Self::Exclude),
+            "TRASH_INCLUDE" => Some(Self::Include),
+            "TRASH_ONLY" => Some(Self::Only),
+            _ => None,
+        }
+    }
+}
+/// how the tags_id of a QueryArticle pick articles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TagMatch {
+    /// articles with every one of the tags
+    All = 0,
+    /// articles with at least one of the tags
+    Any = 1,
+    /// articles with none of the tags
+    None = 2,
+}
+impl TagMatch {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            TagMatch::All => "TAG_MATCH_ALL",
+            TagMatch::Any => "TAG_MATCH_ANY",
+            TagMatch::None => "TAG_MATCH_NONE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TAG_MATCH_ALL" => Some(Self::All),
+            "TAG_MATCH_ANY" => Some(Self::Any),
+            "TAG_MATCH_NONE" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+/// who is asking, and so which articles they may see
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Visibility {
+    /// the management pages: every article
+    All = 0,
+    /// anonymous readers: PUBLISHED articles between their publish_at and expire_at
+    Public = 1,
+}
+impl Visibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Visibility::All => "VISIBILITY_ALL",
+            Visibility::Public => "VISIBILITY_PUBLIC",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VISIBILITY_ALL" => Some(Self::All),
+            "VISIBILITY_PUBLIC" => Some(Self::Public),
+            _ => None,
         }
     }
 }
@@ -338,6 +848,118 @@ pub mod blog_service_client {
             let path = http::uri::PathAndQuery::from_static("/models.BlogService/Delete");
             self.inner.unary(request.into_request(), path, codec).await
         }
+        pub async fn restore(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreRequest>,
+        ) -> Result<tonic::Response<super::RestoreResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/Restore");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn search(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/Search");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn list_revisions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRevisionsRequest>,
+        ) -> Result<tonic::Response<super::ListRevisionsResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/ListRevisions");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn restore_revision(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreRevisionRequest>,
+        ) -> Result<tonic::Response<super::RestoreRevisionResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/RestoreRevision");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn merge_tags(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MergeTagsRequest>,
+        ) -> Result<tonic::Response<super::MergeTagsResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/MergeTags");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn resolve_tags(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ResolveTagsRequest>,
+        ) -> Result<tonic::Response<super::ResolveTagsResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/ResolveTags");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn archive(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ArchiveRequest>,
+        ) -> Result<tonic::Response<super::ArchiveResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/Archive");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchRequest>,
+        ) -> Result<tonic::Response<super::BatchResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/models.BlogService/Batch");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -363,6 +985,38 @@ pub mod blog_service_server {
             &self,
             request: tonic::Request<super::DeleteRequest>,
         ) -> Result<tonic::Response<super::DeleteResponse>, tonic::Status>;
+        async fn restore(
+            &self,
+            request: tonic::Request<super::RestoreRequest>,
+        ) -> Result<tonic::Response<super::RestoreResponse>, tonic::Status>;
+        async fn search(
+            &self,
+            request: tonic::Request<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status>;
+        async fn list_revisions(
+            &self,
+            request: tonic::Request<super::ListRevisionsRequest>,
+        ) -> Result<tonic::Response<super::ListRevisionsResponse>, tonic::Status>;
+        async fn restore_revision(
+            &self,
+            request: tonic::Request<super::RestoreRevisionRequest>,
+        ) -> Result<tonic::Response<super::RestoreRevisionResponse>, tonic::Status>;
+        async fn merge_tags(
+            &self,
+            request: tonic::Request<super::MergeTagsRequest>,
+        ) -> Result<tonic::Response<super::MergeTagsResponse>, tonic::Status>;
+        async fn resolve_tags(
+            &self,
+            request: tonic::Request<super::ResolveTagsRequest>,
+        ) -> Result<tonic::Response<super::ResolveTagsResponse>, tonic::Status>;
+        async fn archive(
+            &self,
+            request: tonic::Request<super::ArchiveRequest>,
+        ) -> Result<tonic::Response<super::ArchiveResponse>, tonic::Status>;
+        async fn batch(
+            &self,
+            request: tonic::Request<super::BatchRequest>,
+        ) -> Result<tonic::Response<super::BatchResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct BlogServiceServer<T: BlogService> {
@@ -541,6 +1195,258 @@ pub mod blog_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/models.BlogService/Restore" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::RestoreRequest> for RestoreSvc<T> {
+                        type Response = super::RestoreResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).restore(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/Search" => {
+                    #[allow(non_camel_case_types)]
+                    struct SearchSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::SearchRequest> for SearchSvc<T> {
+                        type Response = super::SearchResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SearchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).search(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SearchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/ListRevisions" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListRevisionsSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::ListRevisionsRequest>
+                        for ListRevisionsSvc<T>
+                    {
+                        type Response = super::ListRevisionsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRevisionsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_revisions(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListRevisionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/RestoreRevision" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreRevisionSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::RestoreRevisionRequest>
+                        for RestoreRevisionSvc<T>
+                    {
+                        type Response = super::RestoreRevisionResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreRevisionRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).restore_revision(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreRevisionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/MergeTags" => {
+                    #[allow(non_camel_case_types)]
+                    struct MergeTagsSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::MergeTagsRequest> for MergeTagsSvc<T> {
+                        type Response = super::MergeTagsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MergeTagsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).merge_tags(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MergeTagsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/ResolveTags" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResolveTagsSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::ResolveTagsRequest> for ResolveTagsSvc<T> {
+                        type Response = super::ResolveTagsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ResolveTagsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).resolve_tags(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ResolveTagsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/Archive" => {
+                    #[allow(non_camel_case_types)]
+                    struct ArchiveSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::ArchiveRequest> for ArchiveSvc<T> {
+                        type Response = super::ArchiveResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ArchiveRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).archive(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ArchiveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/models.BlogService/Batch" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchSvc<T: BlogService>(pub Arc<T>);
+                    impl<T: BlogService> tonic::server::UnaryService<super::BatchRequest> for BatchSvc<T> {
+                        type Response = super::BatchResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).batch(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
@@ -1,188 +1,659 @@
-use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use chrono::{
+    DateTime, Duration, Local, Months, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
 use prost_types::Timestamp;
 use serde::ser::SerializeStruct;
-use serde::Serializer;
+use serde::{Deserialize, Serialize, Serializer};
 use sqlx::postgres::PgRow;
-use sqlx::{Error, FromRow, Row};
+use sqlx::{Error, FromRow, Postgres, QueryBuilder, Row};
 
-use crate::{get_summary, Article, ArticleState, QueryArticle, QueryCategory, QueryTag};
+use crate::{
+    get_summary, Article, ArticleProjection, ArticleRevision, ArticleState, QueryArticle,
+    QueryCategory, QueryTag, SortDirection, SortKey, TagMatch, Trash, Visibility,
+};
 
 /* =================================================================
 
 
-Query to Sql String
+Query to Sql with bound parameters
 
 
 ================================================================== */
+/// Push the conditions (or assignments) of a request onto a `QueryBuilder`.
+///
+/// Every user supplied value goes through `push_bind`, never into the Sql text.
 pub trait ToSql {
-    fn to_sql(self) -> String;
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>);
 }
 
 impl ToSql for QueryArticle {
-    fn to_sql(self) -> String {
-        let ids = if self.ids.is_empty() {
-            "True".to_string()
-        } else {
-            format!(
-                "id IN ({})",
-                self.ids
-                    .iter()
-                    .map(|id| id.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        };
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push("True");
 
-        let title = if self.title.is_empty() {
-            "True".to_string()
-        } else {
-            format!("title like '%{}%'", self.title)
-        };
+        if !self.ids.is_empty() {
+            builder.push(" AND id = ANY(").push_bind(self.ids).push(")");
+        }
 
-        let state = if self.state == 0 {
-            "True".to_string()
-        } else {
-            format!("state = {}", self.state)
-        };
+        if !self.title.is_empty() {
+            builder
+                .push(" AND title LIKE ")
+                .push_bind(like_pattern(&self.title));
+        }
 
-        let created_year = if self.created_year.is_none() {
-            "True".to_string()
-        } else {
-            let ts = self.created_year.as_ref().unwrap();
-            let year = to_chrono(ts).year();
-            let local_start = Local
-                .from_local_datetime(
-                    &chrono::NaiveDate::from_ymd_opt(year, 1, 1)
-                        .unwrap()
-                        .and_hms_opt(0, 0, 0)
-                        .unwrap(),
-                )
-                .unwrap();
-            let local_end = local_start + Duration::days(365);
+        if !self.tags_id.is_empty() {
+            push_tag_match(builder, self.tags_id, self.tag_match);
+        }
 
-            format!(
-                "created_at >= '{}' AND created_at < '{}'",
-                local_start, local_end
-            )
-        };
+        let states = self
+            .state_filter
+            .map(|filter| filter.states)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|state| AS::try_from(state).ok())
+            .map(|state| state.name().to_string())
+            .collect::<Vec<_>>();
+        if !states.is_empty() {
+            builder
+                .push(" AND state::TEXT = ANY(")
+                .push_bind(states)
+                .push(")");
+        }
 
-        let category_id = if self.category_id == 0 {
-            "True".to_string()
-        } else {
-            format!("category_id = {}", self.category_id)
-        };
+        let timezone = timezone_or_utc(&self.timezone);
+        if let Some((start, end)) = calendar_range(self.year, self.month) {
+            push_local_bound(builder, ">=", start, timezone);
+            push_local_bound(builder, "<", end, timezone);
+        }
+        if let Some(from) = parse_date(&self.from_date) {
+            push_local_bound(builder, ">=", from, timezone);
+        }
+        if let Some(to) = parse_date(&self.to_date) {
+            push_local_bound(builder, "<", to + Duration::days(1), timezone);
+        }
+
+        if self.category_id != 0 && self.include_descendants {
+            builder.push(" AND category_id IN (");
+            push_subtree(builder, self.category_id);
+            builder.push(")");
+        } else if self.category_id != 0 {
+            builder
+                .push(" AND category_id = ")
+                .push_bind(self.category_id);
+        }
+
+        push_trash(builder, self.trash);
 
-        format!(
-            "{} AND {} AND {} AND {} AND {}",
-            ids, title, state, created_year, category_id
-        )
+        if let Some(Visibility::Public) = Visibility::from_i32(self.visibility) {
+            builder.push(" AND ").push(PUBLIC_ARTICLES);
+        }
     }
 }
 
 impl ToSql for QueryCategory {
-    fn to_sql(self) -> String {
-        let ids = if self.ids.is_empty() {
-            "True".to_string()
-        } else {
-            format!(
-                "id IN ({})",
-                self.ids
-                    .iter()
-                    .map(|id| id.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        };
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push("True");
 
-        let name = if self.name.is_empty() {
-            "True".to_string()
-        } else {
-            format!("name like '%{}%'", self.name)
-        };
+        if !self.ids.is_empty() {
+            builder.push(" AND id = ANY(").push_bind(self.ids).push(")");
+        }
 
-        format!("{} AND {}", ids, name)
+        if !self.name.is_empty() {
+            builder
+                .push(" AND name LIKE ")
+                .push_bind(like_pattern(&self.name));
+        }
+        push_trash(builder, self.trash);
     }
 }
 
 impl ToSql for QueryTag {
-    fn to_sql(self) -> String {
-        let ids = if self.ids.is_empty() {
-            "True".to_string()
-        } else {
-            format!(
-                "id IN ({})",
-                self.ids
-                    .iter()
-                    .map(|id| id.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        };
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push("True");
 
-        let name = if self.name.is_empty() {
-            "True".to_string()
-        } else {
-            format!("name like '%{}%'", self.name)
-        };
+        if !self.ids.is_empty() {
+            builder.push(" AND id = ANY(").push_bind(self.ids).push(")");
+        }
+
+        if !self.name.is_empty() {
+            builder
+                .push(" AND name LIKE ")
+                .push_bind(like_pattern(&self.name));
+        }
+        push_trash(builder, self.trash);
+    }
+}
+
+/// Condition on blog.articles for `Visibility::Public`, also holding before the publisher catches up.
+pub const PUBLIC_ARTICLES: &str = "state = 'published' \
+    AND (publish_at IS NULL OR publish_at <= NOW()) \
+    AND (expire_at IS NULL OR expire_at > NOW())";
 
-        format!("{} AND {}", ids, name)
+/// Columns of blog.articles to SELECT for an `ArticleProjection`.
+pub fn article_columns(projection: i32) -> &'static str {
+    match ArticleProjection::from_i32(projection) {
+        Some(ArticleProjection::Summary) => {
+            "id, title, summary, state, created_at, updated_at, category_id, publish_at, expire_at, version"
+        }
+        _ => "*",
     }
 }
+
+/// Ids of a category and all its subcategories; UNION stops on a cycle.
+fn push_subtree(builder: &mut QueryBuilder<'_, Postgres>, category_id: i32) {
+    builder
+        .push("WITH RECURSIVE subtree AS (SELECT id FROM blog.categories WHERE id = ")
+        .push_bind(category_id)
+        .push(
+            " UNION SELECT c.id FROM blog.categories AS c JOIN subtree ON c.parent_id = subtree.id) \
+            SELECT id FROM subtree",
+        );
+}
+
 /* =================================================================
 
 
-Update to Sql String
+Calendar filters
 
 
 ================================================================== */
-impl ToSql for Article {
-    fn to_sql(self) -> String {
-        let update_at = format!("updated_at = '{}',", Local::now());
+/// Fails on calendar filters of a `QueryArticle` that name no day, so they are not silently dropped.
+pub fn check_calendar(req: &QueryArticle) -> Result<(), String> {
+    if req.month != 0 && calendar_range(req.year, req.month).is_none() {
+        return Err(format!("No such month: {}-{}", req.year, req.month));
+    }
+    if req.year != 0 && calendar_range(req.year, 0).is_none() {
+        return Err(format!("No such year: {}", req.year));
+    }
+    for date in [&req.from_date, &req.to_date] {
+        if !date.is_empty() && parse_date(date).is_none() {
+            return Err(format!("Not a YYYY-MM-DD date: {}", date));
+        }
+    }
+    Ok(())
+}
 
-        let title = if self.title.is_empty() {
-            "".to_string()
-        } else {
-            format!("title = '{}',", self.title)
+pub fn timezone_or_utc(timezone: &str) -> &str {
+    match timezone {
+        "" => "UTC",
+        timezone => timezone,
+    }
+}
+
+/// First day of the year, or of the month of it, and the first day after.
+pub fn calendar_range(year: i32, month: i32) -> Option<(NaiveDate, NaiveDate)> {
+    if year == 0 {
+        return None;
+    }
+    let (month, months) = match month {
+        0 => (1, 12),
+        month => (u32::try_from(month).ok()?, 1),
+    };
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let end = start.checked_add_months(Months::new(months))?;
+    Some((start, end))
+}
+
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// `created_at` against the midnight starting `date` in `timezone`, so calendar days follow its
+/// offsets and daylight saving.
+fn push_local_bound(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    op: &str,
+    date: NaiveDate,
+    timezone: &str,
+) {
+    builder
+        .push(format!(" AND created_at {} (", op))
+        .push_bind(date.and_hms_opt(0, 0, 0).unwrap())
+        .push(" AT TIME ZONE ")
+        .push_bind(timezone.to_string())
+        .push(")");
+}
+
+/// Condition on the tags of the articles: all, any or none of `tags_id`.
+fn push_tag_match(builder: &mut QueryBuilder<'_, Postgres>, mut tags_id: Vec<i32>, tag_match: i32) {
+    tags_id.sort_unstable();
+    tags_id.dedup();
+    let tag_count = tags_id.len() as i64;
+    let op = match TagMatch::from_i32(tag_match) {
+        Some(TagMatch::None) => " AND id NOT IN",
+        _ => " AND id IN",
+    };
+    builder
+        .push(op)
+        .push(" (SELECT article_id FROM blog.article_tag WHERE tag_id = ANY(")
+        .push_bind(tags_id)
+        .push(")");
+    if let Some(TagMatch::All) | None = TagMatch::from_i32(tag_match) {
+        builder
+            .push(" GROUP BY article_id HAVING COUNT(*) = ")
+            .push_bind(tag_count);
+    }
+    builder.push(")");
+}
+
+/// Condition on `deleted_at` for the `Trash` of a query.
+fn push_trash(builder: &mut QueryBuilder<'_, Postgres>, trash: i32) {
+    match Trash::from_i32(trash) {
+        Some(Trash::Include) => {}
+        Some(Trash::Only) => {
+            builder.push(" AND deleted_at IS NOT NULL");
+        }
+        _ => {
+            builder.push(" AND deleted_at IS NULL");
+        }
+    }
+}
+
+/// `%keyword%`, with the LIKE wildcards and the escape character in `keyword` matched literally.
+fn like_pattern(keyword: &str) -> String {
+    let escaped = keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/* =================================================================
+
+
+Field masks
+
+
+================================================================== */
+/// Fields of an `Article` an update mask may list.
+pub const ARTICLE_FIELDS: [&str; 8] = [
+    "title",
+    "content",
+    "summary",
+    "state",
+    "category_id",
+    "tags_id",
+    "publish_at",
+    "expire_at",
+];
+
+/// Fields of a `Category` an update mask may list.
+pub const CATEGORY_FIELDS: [&str; 2] = ["name", "parent_id"];
+
+/// Fields of a `Tag` an update mask may list.
+pub const NAME_FIELDS: [&str; 1] = ["name"];
+
+/// Fails on a path that is not one of `fields`.
+pub fn check_mask(paths: &[String], fields: &[&str]) -> Result<(), String> {
+    match paths.iter().find(|path| !fields.contains(&path.as_str())) {
+        Some(path) => Err(format!("Unknown field in update_mask: {}", path)),
+        None => Ok(()),
+    }
+}
+
+/// A value to write restricted to the `paths` of an update mask.
+pub struct Masked<'a, T> {
+    pub value: T,
+    pub paths: &'a [String],
+}
+
+impl<T> Masked<'_, T> {
+    fn has(&self, field: &str) -> bool {
+        self.paths.iter().any(|path| path == field)
+    }
+}
+
+/* =================================================================
+
+
+Keyset pagination
+
+
+================================================================== */
+/// Position of the last article of a page, handed out as the opaque `next_cursor`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    sort_key: i32,
+    value: String,
+    id: i32,
+}
+
+impl Cursor {
+    pub fn from_article(article: &Article, sort_key: SortKey) -> Self {
+        let value = match sort_key {
+            SortKey::CreatedAt => cursor_time(article.created_at.as_ref()),
+            SortKey::UpdatedAt => cursor_time(article.updated_at.as_ref()),
+            SortKey::Title => article.title.clone(),
+            SortKey::Id => String::new(),
         };
+        Self {
+            sort_key: sort_key as i32,
+            value,
+            id: article.id,
+        }
+    }
 
-        let content = if self.content.is_empty() {
-            "".to_string()
+    pub fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid cursor: {}", cursor);
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+fn cursor_time(time: Option<&Timestamp>) -> String {
+    time.and_then(|ts| DateTime::<Utc>::from_timestamp(ts.seconds, ts.nanos as _))
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+enum CursorValue {
+    Time(DateTime<Utc>),
+    Text(String),
+    Id,
+}
+
+/// Sort key, direction, page size and cursor of a `QueryArticle`, kept apart from its filters.
+pub struct Pagination {
+    sort_key: SortKey,
+    direction: SortDirection,
+    page_size: i32,
+    after: Option<(CursorValue, i32)>,
+}
+
+impl Pagination {
+    pub fn from_query(query: &QueryArticle) -> Result<Self, String> {
+        let sort_key = SortKey::from_i32(query.sort_key)
+            .ok_or_else(|| format!("No such sort key: {}", query.sort_key))?;
+        let direction = SortDirection::from_i32(query.sort_direction)
+            .ok_or_else(|| format!("No such sort direction: {}", query.sort_direction))?;
+        if query.page_size < 0 {
+            return Err(format!("Negative page size: {}", query.page_size));
+        }
+
+        let after = if query.cursor.is_empty() {
+            None
         } else {
-            format!("content = '{}',", self.content)
+            let cursor = Cursor::decode(&query.cursor)?;
+            if cursor.sort_key != sort_key as i32 {
+                return Err("Cursor does not belong to this sort key".to_string());
+            }
+            let value = match sort_key {
+                SortKey::CreatedAt | SortKey::UpdatedAt => CursorValue::Time(
+                    DateTime::parse_from_rfc3339(&cursor.value)
+                        .map_err(|_| format!("Invalid cursor: {}", query.cursor))?
+                        .with_timezone(&Utc),
+                ),
+                SortKey::Title => CursorValue::Text(cursor.value),
+                SortKey::Id => CursorValue::Id,
+            };
+            Some((value, cursor.id))
         };
 
-        let summary = if self.summary.is_empty() && self.content.is_empty() {
-            "".to_string()
-        } else if !self.summary.is_empty() {
-            format!("summary = '{}',", self.summary)
-        } else {
-            format!("summary = '{}',", get_summary(&self.content))
+        Ok(Self {
+            sort_key,
+            direction,
+            page_size: query.page_size,
+            after,
+        })
+    }
+
+    fn column(&self) -> &'static str {
+        match self.sort_key {
+            SortKey::CreatedAt => "created_at",
+            SortKey::UpdatedAt => "updated_at",
+            SortKey::Title => "title",
+            SortKey::Id => "id",
+        }
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self.direction {
+            SortDirection::Desc => "DESC",
+            SortDirection::Asc => "ASC",
+        }
+    }
+
+    /// ` AND (column, id) < (value, id)` for the rows after the cursor, `>` when ascending.
+    pub fn push_cursor(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let op = match self.direction {
+            SortDirection::Desc => "<",
+            SortDirection::Asc => ">",
         };
+        match &self.after {
+            None => {}
+            Some((CursorValue::Id, id)) => {
+                builder.push(format!(" AND id {} ", op)).push_bind(*id);
+            }
+            Some((CursorValue::Time(time), id)) => {
+                builder
+                    .push(format!(" AND ({}, id) {} (", self.column(), op))
+                    .push_bind(*time)
+                    .push(", ")
+                    .push_bind(*id)
+                    .push(")");
+            }
+            Some((CursorValue::Text(text), id)) => {
+                builder
+                    .push(format!(" AND ({}, id) {} (", self.column(), op))
+                    .push_bind(text.clone())
+                    .push(", ")
+                    .push_bind(*id)
+                    .push(")");
+            }
+        }
+    }
 
-        let state = if self.state == ArticleState::All as i32 {
-            "".to_string()
-        } else {
-            format!(
-                "state = '{}',",
-                ArticleState::try_from(self.state)
-                    .unwrap()
-                    .as_str_name()
-                    .to_lowercase()
-            )
+    pub fn push_order(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self.sort_key {
+            SortKey::Id => builder.push(format!(" ORDER BY id {}", self.keyword())),
+            _ => builder.push(format!(
+                " ORDER BY {} {}, id {}",
+                self.column(),
+                self.keyword(),
+                self.keyword()
+            )),
+        };
+    }
+
+    pub fn push_limit(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        if self.page_size > 0 {
+            builder.push(" LIMIT ").push_bind(self.page_size as i64);
+        }
+    }
+
+    /// Whether `article` comes after the cursor, as `push_cursor` tells for a row.
+    pub fn is_after(&self, article: &Article) -> bool {
+        let (value, id) = match &self.after {
+            None => return true,
+            Some(after) => after,
         };
+        let ord = match value {
+            CursorValue::Id => article.id.cmp(id),
+            CursorValue::Time(time) => (self.time_of(article), article.id).cmp(&(*time, *id)),
+            CursorValue::Text(text) => (article.title.as_str(), article.id).cmp(&(text, *id)),
+        };
+        match self.direction {
+            SortDirection::Desc => ord == Ordering::Less,
+            SortDirection::Asc => ord == Ordering::Greater,
+        }
+    }
+
+    /// Order of two articles under `push_order`. Titles compare by their bytes, where Postgres
+    /// follows the collation of the database, so mixed case or non-ASCII titles may sort apart.
+    pub fn compare(&self, a: &Article, b: &Article) -> Ordering {
+        let ord = match self.sort_key {
+            SortKey::CreatedAt | SortKey::UpdatedAt => self.time_of(a).cmp(&self.time_of(b)),
+            SortKey::Title => a.title.cmp(&b.title),
+            SortKey::Id => Ordering::Equal,
+        }
+        .then(a.id.cmp(&b.id));
+        match self.direction {
+            SortDirection::Desc => ord.reverse(),
+            SortDirection::Asc => ord,
+        }
+    }
+
+    /// Number of articles `push_limit` keeps, `None` for all of them.
+    pub fn limit(&self) -> Option<usize> {
+        (self.page_size > 0).then_some(self.page_size as usize)
+    }
+
+    fn time_of(&self, article: &Article) -> DateTime<Utc> {
+        let time = match self.sort_key {
+            SortKey::UpdatedAt => article.updated_at.as_ref(),
+            _ => article.created_at.as_ref(),
+        };
+        time.and_then(|ts| DateTime::<Utc>::from_timestamp(ts.seconds, ts.nanos as _))
+            .unwrap_or_default()
+    }
+}
+
+/* =================================================================
+
 
-        let category_id = if self.category_id == 0 {
-            "".to_string()
+Update to Sql with bound parameters
+
+
+================================================================== */
+impl ToSql for Article {
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let summary = if !self.summary.is_empty() {
+            Some(self.summary)
+        } else if !self.content.is_empty() {
+            Some(get_summary(&self.content))
         } else {
-            format!("category_id = {},", self.category_id)
+            None
         };
 
-        format!(
-            "{}{}{}{}{}{}",
-            update_at, title, content, summary, state, category_id
-        )
-        .trim_end_matches(',')
-        .to_string()
+        let mut assignments = builder.separated(", ");
+        assignments
+            .push("updated_at = ")
+            .push_bind_unseparated(Local::now());
+
+        if !self.title.is_empty() {
+            assignments
+                .push("title = ")
+                .push_bind_unseparated(self.title);
+        }
+
+        if !self.content.is_empty() {
+            assignments
+                .push("content = ")
+                .push_bind_unseparated(self.content);
+        }
+
+        if let Some(summary) = summary {
+            assignments
+                .push("summary = ")
+                .push_bind_unseparated(summary);
+        }
+
+        if let Ok(state) = AS::try_from(self.state) {
+            assignments.push("state = ").push_bind_unseparated(state);
+        }
+
+        if self.category_id != 0 {
+            assignments
+                .push("category_id = ")
+                .push_bind_unseparated(self.category_id);
+        }
+
+        if let Some(publish_at) = self.publish_at.as_ref() {
+            assignments
+                .push("publish_at = ")
+                .push_bind_unseparated(to_chrono(publish_at));
+        }
+
+        if let Some(expire_at) = self.expire_at.as_ref() {
+            assignments
+                .push("expire_at = ")
+                .push_bind_unseparated(to_chrono(expire_at));
+        }
+    }
+}
+
+/// The listed fields only, and as they are: an empty summary or a zero category_id is written too.
+/// `tags_id` is left to the caller, living in blog.article_tag.
+impl ToSql for Masked<'_, Article> {
+    fn to_sql(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        let has = |field| self.has(field);
+        let (title, content, summary, state, category_id, publish_at, expire_at) = (
+            has("title"),
+            has("content"),
+            has("summary"),
+            has("state"),
+            has("category_id"),
+            has("publish_at"),
+            has("expire_at"),
+        );
+        let article = self.value;
+
+        let mut assignments = builder.separated(", ");
+        assignments
+            .push("updated_at = ")
+            .push_bind_unseparated(Local::now());
+
+        if title {
+            assignments
+                .push("title = ")
+                .push_bind_unseparated(article.title);
+        }
+
+        if content {
+            assignments
+                .push("content = ")
+                .push_bind_unseparated(article.content);
+        }
+
+        if summary {
+            assignments
+                .push("summary = ")
+                .push_bind_unseparated(article.summary);
+        }
+
+        if state {
+            if let Ok(state) = AS::try_from(article.state) {
+                assignments.push("state = ").push_bind_unseparated(state);
+            }
+        }
+
+        if category_id {
+            assignments
+                .push("category_id = ")
+                .push_bind_unseparated(article.category_id);
+        }
+
+        if publish_at {
+            assignments
+                .push("publish_at = ")
+                .push_bind_unseparated(article.publish_at.as_ref().map(to_chrono));
+        }
+
+        if expire_at {
+            assignments
+                .push("expire_at = ")
+                .push_bind_unseparated(article.expire_at.as_ref().map(to_chrono));
+        }
     }
 }
 
@@ -193,11 +664,53 @@ util time transfer functions
 
 
 ================================================================== */
-fn to_chrono(time: &Timestamp) -> chrono::DateTime<Local> {
+pub fn to_chrono(time: &Timestamp) -> chrono::DateTime<Local> {
     let ts = time.seconds;
     let nanos = time.nanos;
-    let dt = chrono::NaiveDateTime::from_timestamp_opt(ts, nanos as _).unwrap();
-    chrono::DateTime::<Local>::from_utc(dt, chrono::FixedOffset::east_opt(8 * 3600).unwrap())
+    let dt = DateTime::from_timestamp(ts, nanos as _)
+        .unwrap()
+        .naive_utc();
+    DateTime::<Local>::from_naive_utc_and_offset(dt, display_offset(&dt))
+}
+
+/// The timezone times are shown and typed in: the IANA name in `TIMEZONE` of the environment,
+/// the one the public pages count calendar months in, or UTC+8 as ever when it is not one.
+pub fn display_timezone() -> Tz {
+    static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+    *TIMEZONE.get_or_init(|| timezone_or_default(std::env::var("TIMEZONE").ok().as_deref()))
+}
+
+fn timezone_or_default(name: Option<&str>) -> Tz {
+    name.and_then(|name| name.parse().ok())
+        .unwrap_or(chrono_tz::Asia::Shanghai)
+}
+
+/// Offset of `display_timezone` at the UTC time `utc`, which daylight saving may change.
+fn display_offset(utc: &NaiveDateTime) -> chrono::FixedOffset {
+    display_timezone().offset_from_utc_datetime(utc).fix()
+}
+
+/// `%Y-%m-%d %H:%M` in `display_timezone`, the format articles are serialized with.
+pub fn format_minutes(time: &Timestamp) -> String {
+    format_minutes_in(time, display_timezone())
+}
+
+/// `format_minutes` in `timezone`.
+pub fn format_minutes_in(time: &Timestamp, timezone: Tz) -> String {
+    let utc = to_chrono(time).naive_utc();
+    let local = timezone.from_utc_datetime(&utc);
+    local.format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Inverse of `format_minutes`, also taking the `T` separator of `<input type="datetime-local">`.
+pub fn parse_minutes(text: &str) -> Option<Timestamp> {
+    let text = text.trim().replace('T', " ");
+    let naive = chrono::NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M").ok()?;
+    let time = naive.and_local_timezone(display_timezone()).single()?;
+    Some(Timestamp {
+        seconds: time.timestamp(),
+        nanos: 0,
+    })
 }
 
 pub fn to_timestamp(time: chrono::DateTime<Local>) -> Timestamp {
@@ -216,20 +729,52 @@ sqlx transfer functions
 
 
 ================================================================== */
-#[derive(sqlx::Type, Debug)]
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "article_state", rename_all = "lowercase")]
 pub enum AS {
-    All,
     Published,
     Hidden,
+    Scheduled,
+    Draft,
+    Archived,
+}
+
+impl AS {
+    /// The label of the value in blog.article_state.
+    pub fn name(self) -> &'static str {
+        match self {
+            AS::Published => "published",
+            AS::Hidden => "hidden",
+            AS::Scheduled => "scheduled",
+            AS::Draft => "draft",
+            AS::Archived => "archived",
+        }
+    }
+
+    /// Whether an article may be moved from this state to `to`; staying put is always fine.
+    pub fn can_become(self, to: AS) -> bool {
+        use AS::*;
+
+        self == to
+            || matches!(
+                (self, to),
+                (Draft, Scheduled | Published)
+                    | (Scheduled, Draft | Published)
+                    | (Published, Hidden | Archived)
+                    | (Hidden, Draft | Published | Archived)
+                    | (Archived, Hidden | Published)
+            )
+    }
 }
 
 impl From<AS> for ArticleState {
     fn from(value: AS) -> Self {
         match value {
-            AS::All => ArticleState::All,
             AS::Published => ArticleState::Published,
             AS::Hidden => ArticleState::Hidden,
+            AS::Scheduled => ArticleState::Scheduled,
+            AS::Draft => ArticleState::Draft,
+            AS::Archived => ArticleState::Archived,
         }
     }
 }
@@ -237,23 +782,21 @@ impl From<AS> for ArticleState {
 impl TryFrom<i32> for ArticleState {
     type Error = String;
     fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(ArticleState::All),
-            1 => Ok(ArticleState::Published),
-            2 => Ok(ArticleState::Hidden),
-            _ => Err("No such state".to_string()),
-        }
+        ArticleState::from_i32(value).ok_or_else(|| "No such state".to_string())
     }
 }
 
+/// Fails on `ArticleState::Unspecified`, which is not stored.
 impl TryFrom<i32> for AS {
     type Error = String;
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            0 => Ok(AS::All),
             1 => Ok(AS::Published),
             2 => Ok(AS::Hidden),
+            3 => Ok(AS::Scheduled),
+            4 => Ok(AS::Draft),
+            5 => Ok(AS::Archived),
             other => Err(format!("AS: {} not implement", other)),
         }
     }
@@ -264,18 +807,51 @@ impl FromRow<'_, PgRow> for Article {
         let created_at = row.try_get::<DateTime<Local>, _>("created_at")?;
         let updated_at = row.try_get::<DateTime<Local>, _>("updated_at")?;
         let state = ArticleState::from(row.try_get::<AS, _>("state")?) as i32;
-        let tag_ids = row.try_get::<Vec<i32>, _>("tag_ids")?;
+        // articles without any row in blog.article_tag come back with a NULL tag_ids
+        let tag_ids = row
+            .try_get::<Option<Vec<i32>>, _>("tag_ids")?
+            .unwrap_or_default();
 
         Ok(Article {
             id: row.try_get("id")?,
             title: row.try_get("title")?,
-            content: row.try_get("content")?,
+            content: match row.try_get("content") {
+                // left out by `ArticleProjection::Summary`
+                Err(Error::ColumnNotFound(_)) => String::new(),
+                content => content?,
+            },
             summary: row.try_get("summary")?,
             state,
             created_at: Some(to_timestamp(created_at)),
             updated_at: Some(to_timestamp(updated_at)),
             category_id: row.try_get("category_id")?,
             tags_id: tag_ids,
+            publish_at: row
+                .try_get::<Option<DateTime<Local>>, _>("publish_at")?
+                .map(to_timestamp),
+            expire_at: row
+                .try_get::<Option<DateTime<Local>>, _>("expire_at")?
+                .map(to_timestamp),
+            version: row.try_get("version")?,
+        })
+    }
+}
+
+impl FromRow<'_, PgRow> for ArticleRevision {
+    fn from_row(row: &'_ PgRow) -> Result<Self, Error> {
+        let created_at = row.try_get::<DateTime<Local>, _>("created_at")?;
+        let state = ArticleState::from(row.try_get::<AS, _>("state")?) as i32;
+
+        Ok(ArticleRevision {
+            id: row.try_get("id")?,
+            article_id: row.try_get("article_id")?,
+            title: row.try_get("title")?,
+            content: row.try_get("content")?,
+            summary: row.try_get("summary")?,
+            state,
+            category_id: row.try_get("category_id")?,
+            tags_id: row.try_get("tags_id")?,
+            created_at: Some(to_timestamp(created_at)),
         })
     }
 }
@@ -288,12 +864,12 @@ Serialize
 
 ================================================================== */
 
-impl serde::Serialize for Article {
+impl Serialize for Article {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Article", 7)?;
+        let mut state = serializer.serialize_struct("Article", 12)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("title", &self.title)?;
         state.serialize_field("content", &self.content)?;
@@ -301,13 +877,38 @@ impl serde::Serialize for Article {
         state.serialize_field("summary", &self.summary)?;
         state.serialize_field("state", &self.state)?;
 
-        let create_at = to_chrono(self.created_at.as_ref().unwrap());
-        let create_at = create_at.format("%Y-%m-%d %H:%M").to_string();
+        let create_at = format_minutes(self.created_at.as_ref().unwrap());
         state.serialize_field("created_at", &create_at)?;
-        let update_at = to_chrono(self.updated_at.as_ref().unwrap());
-        let update_at = update_at.format("%Y-%m-%d %H:%M").to_string();
+        let update_at = format_minutes(self.updated_at.as_ref().unwrap());
         state.serialize_field("updated_at", &update_at)?;
         state.serialize_field("tags_id", &self.tags_id)?;
+        // empty when not set
+        let publish_at = self.publish_at.as_ref().map(format_minutes);
+        state.serialize_field("publish_at", &publish_at.unwrap_or_default())?;
+        let expire_at = self.expire_at.as_ref().map(format_minutes);
+        state.serialize_field("expire_at", &expire_at.unwrap_or_default())?;
+        state.serialize_field("version", &self.version)?;
+        state.end()
+    }
+}
+
+impl Serialize for ArticleRevision {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ArticleRevision", 9)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("article_id", &self.article_id)?;
+        state.serialize_field("title", &self.title)?;
+        state.serialize_field("content", &self.content)?;
+        state.serialize_field("summary", &self.summary)?;
+        state.serialize_field("state", &self.state)?;
+        state.serialize_field("category_id", &self.category_id)?;
+        state.serialize_field("tags_id", &self.tags_id)?;
+
+        let create_at = format_minutes(self.created_at.as_ref().unwrap());
+        state.serialize_field("created_at", &create_at)?;
         state.end()
     }
 }
@@ -350,23 +951,238 @@ mod tests {
                 nanos: 1,
             }),
             tags_id: vec![1],
+            publish_at: Some(Timestamp {
+                seconds: 60,
+                nanos: 0,
+            }),
+            expire_at: None,
+            version: 2,
         };
 
+        // the times are shown in the timezone of the environment
+        let at = |seconds| format_minutes(&Timestamp { seconds, nanos: 0 });
         let json = serde_json::to_string_pretty(&article).unwrap();
-        let res = r#"{
+        let res = format!(
+            r#"{{
   "id": 1,
   "title": "title",
   "content": "content",
   "category_id": 1,
   "summary": "summary",
   "state": 1,
-  "created_at": "1970-01-01 08:00",
-  "updated_at": "1970-01-01 08:00",
+  "created_at": "{}",
+  "updated_at": "{}",
   "tags_id": [
     1
-  ]
-}"#;
+  ],
+  "publish_at": "{}",
+  "expire_at": "",
+  "version": 2
+}}"#,
+            at(1),
+            at(1),
+            at(60)
+        );
         assert_eq!(json, res);
         // println!("{}", json);
     }
+
+    #[test]
+    fn format_minutes_in_should_use_the_timezone() {
+        let time = Timestamp {
+            seconds: 60,
+            nanos: 1,
+        };
+        assert_eq!(
+            format_minutes_in(&time, chrono_tz::Asia::Shanghai),
+            "1970-01-01 08:01"
+        );
+        assert_eq!(
+            format_minutes_in(&time, chrono_tz::America::New_York),
+            "1969-12-31 19:01"
+        );
+    }
+
+    #[test]
+    fn query_article_to_sql_should_bind_values() {
+        let query = QueryArticle {
+            ids: vec![1, 2],
+            title: "it's 100%_done\\".to_string(),
+            category_id: 1,
+            ..QueryArticle::default()
+        };
+        let mut builder = QueryBuilder::new("SELECT * FROM blog.articles WHERE ");
+        query.to_sql(&mut builder);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM blog.articles WHERE True AND id = ANY($1) AND title LIKE $2 AND category_id = $3 AND deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn tag_match_should_restrict_the_query() {
+        let query = |tag_match: TagMatch| {
+            let query = QueryArticle {
+                ids: vec![1],
+                tags_id: vec![2, 1, 2],
+                tag_match: tag_match as i32,
+                ..QueryArticle::default()
+            };
+            let mut builder = QueryBuilder::new("");
+            query.to_sql(&mut builder);
+            builder.sql().to_string()
+        };
+        let tags = "(SELECT article_id FROM blog.article_tag WHERE tag_id = ANY($2)";
+        assert_eq!(
+            query(TagMatch::All),
+            format!(
+                "True AND id = ANY($1) AND id IN {} GROUP BY article_id HAVING COUNT(*) = $3) \
+                 AND deleted_at IS NULL",
+                tags
+            )
+        );
+        assert_eq!(
+            query(TagMatch::Any),
+            format!(
+                "True AND id = ANY($1) AND id IN {}) AND deleted_at IS NULL",
+                tags
+            )
+        );
+        assert_eq!(
+            query(TagMatch::None),
+            format!(
+                "True AND id = ANY($1) AND id NOT IN {}) AND deleted_at IS NULL",
+                tags
+            )
+        );
+    }
+
+    #[test]
+    fn calendar_filters_should_use_calendar_boundaries() {
+        assert_eq!(
+            calendar_range(2024, 2),
+            Some((
+                NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+            ))
+        );
+        // a leap year is 366 days long
+        let (start, end) = calendar_range(2024, 0).unwrap();
+        assert_eq!((end - start).num_days(), 366);
+
+        let query = |year, month, from_date: &str| QueryArticle {
+            year,
+            month,
+            from_date: from_date.to_string(),
+            ..QueryArticle::default()
+        };
+        assert!(check_calendar(&query(2024, 12, "2024-02-29")).is_ok());
+        assert!(check_calendar(&query(0, 2, "")).is_err());
+        assert!(check_calendar(&query(2024, 13, "")).is_err());
+        assert!(check_calendar(&query(0, 0, "2023-02-29")).is_err());
+
+        let mut builder = QueryBuilder::new("");
+        query(2024, 2, "").to_sql(&mut builder);
+        assert_eq!(
+            builder.sql(),
+            "True AND created_at >= ($1 AT TIME ZONE $2) AND created_at < ($3 AT TIME ZONE $4) AND deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn article_to_sql_should_bind_values() {
+        let article = Article {
+            title: "'; DROP TABLE blog.articles; --".to_string(),
+            content: "it's".to_string(),
+            ..Article::default()
+        };
+        let mut builder = QueryBuilder::new("UPDATE blog.articles SET ");
+        article.to_sql(&mut builder);
+
+        assert_eq!(
+            builder.sql(),
+            "UPDATE blog.articles SET updated_at = $1, title = $2, content = $3, summary = $4"
+        );
+    }
+
+    #[test]
+    fn masked_article_to_sql_should_write_listed_fields() {
+        let paths = vec!["summary".to_string(), "category_id".to_string()];
+        let article = Article {
+            title: "ignored".to_string(),
+            ..Article::default()
+        };
+        let mut builder = QueryBuilder::new("UPDATE blog.articles SET ");
+        Masked {
+            value: article,
+            paths: &paths,
+        }
+        .to_sql(&mut builder);
+
+        assert_eq!(
+            builder.sql(),
+            "UPDATE blog.articles SET updated_at = $1, summary = $2, category_id = $3"
+        );
+        assert!(check_mask(&paths, &ARTICLE_FIELDS).is_ok());
+        assert!(check_mask(&["id".to_string()], &ARTICLE_FIELDS).is_err());
+    }
+
+    #[test]
+    fn cursor_should_round_trip() {
+        let article = Article {
+            id: 7,
+            title: "标题 'quoted'".into(),
+            created_at: Some(Timestamp {
+                seconds: 1,
+                nanos: 1000,
+            }),
+            ..Article::default()
+        };
+        let cursor = Cursor::from_article(&article, SortKey::Title);
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+
+        let cursor = Cursor::from_article(&article, SortKey::CreatedAt);
+        assert_eq!(cursor.value, "1970-01-01T00:00:01.000001Z");
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn minutes_should_round_trip() {
+        let time = parse_minutes("2026-10-18T09:30").unwrap();
+        assert_eq!(format_minutes(&time), "2026-10-18 09:30");
+        assert_eq!(parse_minutes("2026-10-18 09:30"), Some(time));
+        assert_eq!(parse_minutes(""), None);
+    }
+
+    #[test]
+    fn display_timezone_should_fall_back_to_utc8() {
+        let timezone = timezone_or_default(Some("Europe/Paris"));
+        assert_eq!(timezone, chrono_tz::Europe::Paris);
+        assert_eq!(timezone_or_default(None), chrono_tz::Asia::Shanghai);
+        assert_eq!(
+            timezone_or_default(Some("Mars/Olympus_Mons")),
+            chrono_tz::Asia::Shanghai
+        );
+    }
+
+    #[test]
+    fn state_transitions_should_follow_the_rules() {
+        assert!(AS::Draft.can_become(AS::Published));
+        assert!(AS::Draft.can_become(AS::Scheduled));
+        assert!(AS::Scheduled.can_become(AS::Published));
+        assert!(AS::Published.can_become(AS::Archived));
+        assert!(AS::Archived.can_become(AS::Published));
+        assert!(AS::Published.can_become(AS::Published));
+
+        assert!(!AS::Published.can_become(AS::Draft));
+        assert!(!AS::Published.can_become(AS::Scheduled));
+        assert!(!AS::Draft.can_become(AS::Archived));
+        assert!(!AS::Archived.can_become(AS::Draft));
+    }
+
+    #[test]
+    fn like_pattern_should_escape_wildcards() {
+        assert_eq!(like_pattern("a%b_c\\d"), "%a\\%b\\_c\\\\d%");
+    }
 }